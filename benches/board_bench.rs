@@ -1,8 +1,9 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 
 use mineraker::{
     board::{Board, GenerationSettings, Seed},
     position::Position,
+    tile::{State, Tile, Value},
 };
 
 pub fn bench_generate_small_board(c: &mut Criterion) {
@@ -14,6 +15,7 @@ pub fn bench_generate_small_board(c: &mut Criterion) {
                 height: 8,
                 mine_count: 10,
                 start_pos: Position::default(),
+                ..Default::default()
             }))
         })
     });
@@ -28,6 +30,7 @@ pub fn bench_generate_large_board(c: &mut Criterion) {
                 height: 16,
                 mine_count: 170,
                 start_pos: Position::default(),
+                ..Default::default()
             }))
         })
     });
@@ -42,6 +45,7 @@ pub fn bench_cascade_open(c: &mut Criterion) {
                 height: 16,
                 mine_count: 99,
                 start_pos: Position::default(),
+                ..Default::default()
             });
             board.open_from(Position { x: 16, y: 13 });
 
@@ -50,10 +54,91 @@ pub fn bench_cascade_open(c: &mut Criterion) {
     });
 }
 
+/// Sizes of the huge boards, from a million tiles upwards.
+const HUGE_SIZES: [usize; 2] = [1000, 2000];
+
+fn huge_settings(size: usize, mine_count: usize) -> GenerationSettings {
+    GenerationSettings {
+        seed: Seed::new(0),
+        width: size,
+        height: size,
+        mine_count,
+        start_pos: Position::new(size / 2, size / 2),
+        ..Default::default()
+    }
+}
+
+/// Tile as it was stored before packing, with the value and state as separate enums.
+#[allow(dead_code)]
+struct UnpackedTile {
+    value: Value,
+    state: State,
+}
+
+/// Prints the size of a tile and the heap size of the tiles of every huge board, packed
+/// and as they were stored before packing. Criterion only measures time, so the memory
+/// impact is reported next to it.
+pub fn report_tile_memory(_: &mut Criterion) {
+    let packed = size_of::<Tile>();
+    let unpacked = size_of::<UnpackedTile>();
+    println!("Tile size: {} B packed, {} B unpacked", packed, unpacked);
+    for size in HUGE_SIZES {
+        let board = Board::new(&huge_settings(size, size * size / 5));
+        let tiles = board.tiles_and_pos().count();
+        println!(
+            "{0}x{0} board tiles: {1:.1} MiB packed, {2:.1} MiB unpacked",
+            size,
+            (tiles * packed) as f64 / (1 << 20) as f64,
+            (tiles * unpacked) as f64 / (1 << 20) as f64,
+        );
+    }
+}
+
+/// Throughput of huge boards is given in tiles.
+fn tiles(size: usize) -> Throughput {
+    Throughput::Elements((size * size) as u64)
+}
+
+pub fn bench_generate_huge_board(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Generate huge board");
+    group.sample_size(10);
+    for size in HUGE_SIZES {
+        group.throughput(tiles(size));
+        group.bench_function(format!("{0}x{0}", size), |b| {
+            // Expert density of about one mine per five tiles.
+            b.iter(|| black_box(Board::new(&huge_settings(size, size * size / 5))))
+        });
+    }
+    group.finish();
+}
+
+pub fn bench_cascade_open_huge_board(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Cascade open huge board");
+    group.sample_size(10);
+    for size in HUGE_SIZES {
+        // Low density lets the cascade spread over most of the board.
+        let gs = huge_settings(size, size * size / 20);
+        let board = Board::with_empty_at(&gs, gs.start_pos).unwrap();
+
+        group.throughput(tiles(size));
+        group.bench_function(format!("{0}x{0}", size), |b| {
+            b.iter_batched(
+                || board.clone(),
+                |mut board| black_box(board.open_from(gs.start_pos)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
+    report_tile_memory,
     bench_generate_small_board,
     bench_generate_large_board,
     bench_cascade_open,
+    bench_generate_huge_board,
+    bench_cascade_open_huge_board,
 );
 criterion_main!(benches);
@@ -3,6 +3,7 @@ use std::{collections::HashSet, ops::RangeInclusive};
 use crate::position::Position;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MineCount(RangeInclusive<usize>);
 
 impl MineCount {
@@ -60,16 +61,26 @@ impl From<usize> for MineCount {
 /// Stores available action for [`Area`]. Some [`Area`]s do not
 /// have available actions.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AreaAction {
     Open,
     Flag,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Area {
     positions: HashSet<Position>,
     // Stores the number of mines area contains.
     mine_count: MineCount,
+    // Stores the largest number of mines a single position can hold.
+    #[cfg_attr(feature = "serde", serde(default = "one_mine_per_position"))]
+    mines_per_position: usize,
+}
+
+#[cfg(feature = "serde")]
+fn one_mine_per_position() -> usize {
+    1
 }
 
 impl Area {
@@ -95,17 +106,78 @@ impl Area {
     /// let area3 = Area::new(positions.into(), MineCount::from_range(0, 2));
     /// ```
     pub fn new<T>(positions: HashSet<Position>, mine_count: T) -> Self
+    where
+        T: Into<MineCount>,
+    {
+        Self::with_mines_per_position(positions, mine_count, 1)
+    }
+
+    /// Creates a new [`Area`] like [`Area::new`], where every position can hold up to
+    /// `mines_per_position` mines instead of one.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashSet;
+    /// use mineraker::area::{Area, AreaAction};
+    /// use mineraker::position::Position;
+    ///
+    /// let positions = HashSet::from([Position::new(0, 0), Position::new(1, 0)]);
+    ///
+    /// // Two mines could be in one position, so neither is certain.
+    /// let area = Area::with_mines_per_position(positions.clone(), 2, 3);
+    /// assert_eq!(area.next_action(), None);
+    ///
+    /// let full = Area::with_mines_per_position(positions, 6, 3);
+    /// assert_eq!(full.next_action(), Some(AreaAction::Flag));
+    /// ```
+    pub fn with_mines_per_position<T>(
+        positions: HashSet<Position>,
+        mine_count: T,
+        mines_per_position: usize,
+    ) -> Self
     where
         T: Into<MineCount>,
     {
         Self {
             positions,
             mine_count: mine_count.into(),
+            mines_per_position,
         }
     }
 
+    /// Returns the positions of the area.
+    #[inline]
+    pub fn positions(&self) -> &HashSet<Position> {
+        &self.positions
+    }
+
+    /// Returns the number of mines the area contains.
+    #[inline]
+    pub fn mine_count(&self) -> &MineCount {
+        &self.mine_count
+    }
+
+    /// Returns the largest number of mines a single position of the area can hold.
+    #[inline]
+    pub fn mines_per_position(&self) -> usize {
+        self.mines_per_position
+    }
+
+    /// Returns the largest number of mines the area can hold, which is reached when every
+    /// position is full.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.positions.len() * self.mines_per_position
+    }
+
+    /// Returns `true` if the area shares at least one position with `other`.
+    #[inline]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.positions.is_disjoint(&other.positions)
+    }
+
     /// Calculates set difference between two [`Area`]s and returns area from `self` which is not
-    /// in `other`.
+    /// in `other`. Both areas are expected to allow the same number of mines per position.
     ///
     /// # Examples
     /// ```
@@ -119,6 +191,8 @@ impl Area {
     /// assert_eq!(area1.difference(&area2), Area::new(HashSet::from([Position::new(0, 0)]), 1));
     /// ```
     pub fn difference(&self, other: &Self) -> Self {
+        debug_assert_eq!(self.mines_per_position, other.mines_per_position);
+        let per_position = self.mines_per_position;
         let diff: HashSet<_> = self
             .positions
             .difference(&other.positions)
@@ -128,7 +202,7 @@ impl Area {
         let intersection_size = self.positions.intersection(&other.positions).count();
 
         let min = {
-            let intersection_mines = intersection_size
+            let intersection_mines = (intersection_size * per_position)
                 .min(self.mine_count.min())
                 .min(other.mine_count.max());
 
@@ -141,23 +215,28 @@ impl Area {
             // the area that forms it.
             let other_diff_size = other.positions.len() - intersection_size;
             // Use `saturating_sub` to emulate calcuting max between result and 0.
-            let other_mines_overflow_to_intersection =
-                other.mine_count.min().saturating_sub(other_diff_size);
+            let other_mines_overflow_to_intersection = other
+                .mine_count
+                .min()
+                .saturating_sub(other_diff_size * per_position);
 
             // Substraction can't underflow as `self.mine_count.max()` includes
             // mines that could possibly be in the intersection area and therefore
             // it is always greater or equal to mine count in the intersection.
-            diff.len()
+            (diff.len() * per_position)
                 .min(self.mine_count.max() - other_mines_overflow_to_intersection)
         };
 
         Self {
             positions: diff,
             mine_count: MineCount::from_range(min, max),
+            mines_per_position: per_position,
         }
     }
 
-    /// Returns the next possible action for [`Area`] if one exists.
+    /// Returns the next possible action for [`Area`] if one exists. Positions are flagged
+    /// only when every one of them is full, which on areas with several mines per position
+    /// means that each holds [`Area::mines_per_position`] mines.
     ///
     /// # Examples
     /// ```
@@ -183,7 +262,7 @@ impl Area {
     pub fn next_action(&self) -> Option<AreaAction> {
         self.mine_count.exact_count().and_then(|count| match count {
             0 => Some(AreaAction::Open),
-            _ if count == self.positions.len() => Some(AreaAction::Flag),
+            _ if count == self.capacity() => Some(AreaAction::Flag),
             _ => None,
         })
     }
@@ -192,7 +271,7 @@ impl Area {
 #[cfg(test)]
 mod tests {
     use crate::{
-        area::{Area, MineCount},
+        area::{Area, AreaAction, MineCount},
         position::Position,
     };
     use std::collections::HashSet;
@@ -368,4 +447,40 @@ mod tests {
             assert_eq!(diff, Area::new(diff_3_positions.clone(), 0..=2));
         }
     }
+
+    #[test]
+    fn area_difference_with_several_mines_per_position() {
+        let positions1 = HashSet::from([Position::new(0, 0), Position::new(1, 0)]);
+        let positions2 = HashSet::from([Position::new(1, 0), Position::new(2, 0)]);
+        let area1 = Area::with_mines_per_position(positions1, 4, 3);
+        let area2 = Area::with_mines_per_position(positions2, 1, 3);
+
+        // At most one of the four mines is shared, so the other position is full.
+        let diff = area1.difference(&area2);
+        assert_eq!(
+            diff,
+            Area::with_mines_per_position(HashSet::from([Position::new(0, 0)]), 3, 3)
+        );
+        assert_eq!(diff.next_action(), Some(AreaAction::Flag));
+
+        // The shared position holds at least one mine, which is all of `area2`.
+        let diff = area2.difference(&area1);
+        assert_eq!(
+            diff,
+            Area::with_mines_per_position(HashSet::from([Position::new(2, 0)]), 0, 3)
+        );
+        assert_eq!(diff.next_action(), Some(AreaAction::Open));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn area_serde_round_trip() {
+        let area = Area::new(
+            HashSet::from([Position::new(0, 1), Position::new(2, 3)]),
+            1..=2,
+        );
+        let json = serde_json::to_string(&area).unwrap();
+
+        assert_eq!(serde_json::from_str::<Area>(&json).unwrap(), area);
+    }
 }
@@ -0,0 +1,521 @@
+use crate::position::Position;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// [`BitGrid`] is a set of positions on a board, stored as one bit per tile. Every row
+/// starts at a new word, so whole rows can be shifted and combined a word at a time. Bits
+/// past the width of the board are always zero.
+///
+/// [`Board`] builds grids from its tiles when counting numbers and filling cascades of
+/// bounded rectangles, instead of storing mines, open tiles and flags as grids. Tiles
+/// stay the only copy of the board, which can't get out of sync through
+/// [`Board::get_tile_mut`] or history changes, and packing the tiles of a row costs less
+/// than the shifts done with it. See the huge board benchmarks in `benches/board_bench.rs`.
+///
+/// [`Board`]: crate::board::Board
+/// [`Board::get_tile_mut`]: crate::board::Board::get_tile_mut
+///
+/// # Examples
+/// ```
+/// use mineraker::bitboard::BitGrid;
+/// use mineraker::position::Position;
+///
+/// let mut mines = BitGrid::new(3, 3);
+/// mines.insert(Position::new(0, 0));
+/// mines.insert(Position::new(2, 2));
+///
+/// assert_eq!(mines.neighbor_counts(), [0, 1, 0, 1, 2, 1, 0, 1, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    // Number of words in each row.
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Creates an empty grid with the given width and height.
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    /// Creates a grid from items in row-major order, with the positions of the items
+    /// accepted by `f` in the set. Length of `items` must be a multiple of `width`.
+    pub fn from_slice<T>(width: usize, items: &[T], f: impl Fn(&T) -> bool) -> Self {
+        debug_assert!(width != 0 && items.len().is_multiple_of(width));
+        let mut grid = Self::new(width, items.len() / width);
+        for (row, items) in grid.words.chunks_mut(grid.stride).zip(items.chunks(width)) {
+            pack_bits(items, &f, row);
+        }
+        grid
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns `true` if the position is in the set. Positions outside the grid are never
+    /// in the set.
+    #[inline]
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.x < self.width
+            && pos.y < self.height
+            && self.words[pos.y * self.stride + pos.x / WORD_BITS] >> (pos.x % WORD_BITS) & 1 != 0
+    }
+
+    /// Adds the position to the set.
+    ///
+    /// # Panics
+    /// If the position is outside the grid.
+    #[inline]
+    pub fn insert(&mut self, pos: Position) {
+        assert!(pos.x < self.width && pos.y < self.height);
+        self.words[pos.y * self.stride + pos.x / WORD_BITS] |= 1 << (pos.x % WORD_BITS);
+    }
+
+    /// Removes the position from the set. Positions outside the grid are ignored.
+    #[inline]
+    pub fn remove(&mut self, pos: Position) {
+        if pos.x < self.width && pos.y < self.height {
+            self.words[pos.y * self.stride + pos.x / WORD_BITS] &= !(1 << (pos.x % WORD_BITS));
+        }
+    }
+
+    /// Returns the number of positions in the set.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if the set has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Returns iterator over the positions in the set in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let (y, first_x) = (i / self.stride, i % self.stride * WORD_BITS);
+            BitIter(word).map(move |bit| Position::new(first_x + bit, y))
+        })
+    }
+
+    /// Removes the positions which are not in the other set. Grids must have the same size.
+    pub fn intersect(&mut self, other: &BitGrid) {
+        debug_assert_eq!((self.width, self.height), (other.width, other.height));
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= b;
+        }
+    }
+
+    #[inline]
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Mask of the bits inside the board in the last word of each row.
+    #[inline]
+    fn last_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Returns the row above and below `y`, or empty rows outside the grid.
+    fn vertical_rows(&self, y: usize) -> (&[u64], &[u64]) {
+        let above = if y > 0 { self.row(y - 1) } else { &[] };
+        let below = if y + 1 < self.height {
+            self.row(y + 1)
+        } else {
+            &[]
+        };
+        (above, below)
+    }
+
+    /// Returns the number of neighbors in the set for every tile in row-major order.
+    ///
+    /// The eight neighbor rows are formed by shifting the rows above, at and below each
+    /// tile, and are summed a word at a time with bit-sliced adders, which hold bit `k` of
+    /// the counts of 64 tiles in plane `k`.
+    pub fn neighbor_counts(&self) -> Vec<u8> {
+        let mut counts = vec![0; self.width * self.height];
+        for y in 0..self.height {
+            let (above, below) = self.vertical_rows(y);
+            let row = self.row(y);
+
+            for i in 0..self.stride {
+                let mut planes = [0u64; 4];
+                for rows in [above, below] {
+                    if !rows.is_empty() {
+                        for word in [rows[i], east(rows, i), west(rows, i)] {
+                            add_to_planes(&mut planes, word);
+                        }
+                    }
+                }
+                add_to_planes(&mut planes, east(row, i));
+                add_to_planes(&mut planes, west(row, i));
+                if planes == [0; 4] {
+                    continue;
+                }
+
+                let first = y * self.width + i * WORD_BITS;
+                let bits = (self.width - i * WORD_BITS).min(WORD_BITS);
+                for (byte, counts) in counts[first..first + bits].chunks_mut(8).enumerate() {
+                    let count = planes.iter().enumerate().fold(0, |count, (k, plane)| {
+                        count | SPREAD_BITS[(plane >> (8 * byte)) as u8 as usize] << k
+                    });
+                    counts.copy_from_slice(&count.to_le_bytes()[..counts.len()]);
+                }
+            }
+        }
+        counts
+    }
+
+    /// Returns the set with all neighbors of its positions added.
+    pub fn dilate(&self) -> BitGrid {
+        let mut dilated = Self::new(self.width, self.height);
+        let mask = self.last_mask();
+        for y in 0..self.height {
+            let (above, below) = self.vertical_rows(y);
+            let row = self.row(y);
+            if [row, above, below]
+                .iter()
+                .all(|r| r.iter().all(|&w| w == 0))
+            {
+                continue;
+            }
+            let out = &mut dilated.words[y * self.stride..(y + 1) * self.stride];
+            for (i, word) in out.iter_mut().enumerate() {
+                *word = spread(row, i) | spread(above, i) | spread(below, i);
+            }
+            if let Some(last) = out.last_mut() {
+                *last &= mask;
+            }
+        }
+        dilated
+    }
+
+    /// Returns the set with all positions of `passable` added which are connected to the
+    /// set through neighboring positions of `passable`. Grids must have the same size.
+    ///
+    /// Rows are grown bit-parallel: each row first takes the passable neighbors of the
+    /// rows around it and then fills the passable runs it touches in log steps. Rows next
+    /// to a changed row are queued to grow again until no row changes.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::bitboard::BitGrid;
+    /// use mineraker::position::Position;
+    ///
+    /// // Passable tiles in a diagonal line and a separate corner tile.
+    /// let passable = BitGrid::from_slice(4, &[
+    ///     1, 0, 0, 1,
+    ///     0, 1, 0, 0,
+    ///     0, 0, 1, 0,
+    ///     0, 0, 0, 0,
+    /// ], |&tile| tile == 1);
+    /// let mut seed = BitGrid::new(4, 4);
+    /// seed.insert(Position::new(0, 0));
+    ///
+    /// let area: Vec<_> = seed.flood_fill(&passable).iter().collect();
+    /// assert_eq!(area, [Position::new(0, 0), Position::new(1, 1), Position::new(2, 2)]);
+    /// ```
+    pub fn flood_fill(&self, passable: &BitGrid) -> BitGrid {
+        debug_assert_eq!((self.width, self.height), (passable.width, passable.height));
+        self.clone()
+            .flood_fill_by(|y, row| row.copy_from_slice(passable.row(y)))
+    }
+
+    /// Fills the set like [`BitGrid::flood_fill`], but gets the passable positions one row
+    /// at a time from `passable_row`, which writes the bits of row `y` into the given words,
+    /// for example with [`pack_bits`]. Rows are requested at most once and only when the
+    /// area reaches next to them, so small areas on large boards stay cheap.
+    pub fn flood_fill_by(mut self, mut passable_row: impl FnMut(usize, &mut [u64])) -> BitGrid {
+        let (height, stride) = (self.height, self.stride);
+        let flag_words = height.div_ceil(WORD_BITS);
+        // Passable rows, a buffer row for growing and bitsets of packed and queued rows,
+        // all in one allocation.
+        let mut scratch = vec![0u64; (height + 1) * stride + 2 * flag_words];
+        let (passable, rest) = scratch.split_at_mut(height * stride);
+        let (buffer, rest) = rest.split_at_mut(stride);
+        let (packed, queued) = rest.split_at_mut(flag_words);
+
+        for y in (0..height).filter(|&y| self.row(y).iter().any(|&w| w != 0)) {
+            for y in y.saturating_sub(1)..(y + 2).min(height) {
+                queued[y / WORD_BITS] |= 1 << (y % WORD_BITS);
+            }
+        }
+        // Queued rows are grown by the passable neighbors of the rows around them. Rows
+        // next to a row which changed are queued again.
+        while let Some(i) = queued.iter().position(|&w| w != 0) {
+            let y = i * WORD_BITS + queued[i].trailing_zeros() as usize;
+            queued[i] &= queued[i] - 1;
+            let (word, bit) = (y / WORD_BITS, 1 << (y % WORD_BITS));
+
+            let open = &mut passable[y * stride..(y + 1) * stride];
+            if packed[word] & bit == 0 {
+                packed[word] |= bit;
+                passable_row(y, open);
+            }
+            if self.grow_row(y, open, buffer) {
+                for y in [y.wrapping_sub(1), y + 1] {
+                    if y < height {
+                        queued[y / WORD_BITS] |= 1 << (y % WORD_BITS);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Grows row `y` with the passable neighbors of the rows around it and fills the
+    /// passable runs which it touches. Returns `true` if the row changed.
+    fn grow_row(&mut self, y: usize, open: &[u64], buffer: &mut [u64]) -> bool {
+        let (above, below) = self.vertical_rows(y);
+        let row = self.row(y);
+        let mut grown = false;
+        for (i, word) in buffer.iter_mut().enumerate() {
+            *word = (row[i] | spread(above, i) | spread(below, i)) & open[i];
+            grown |= *word & !row[i] != 0;
+        }
+        if !grown {
+            return false;
+        }
+
+        // Bits of the row which are not passable, such as seeds, stay in the row.
+        fill_runs(buffer, open);
+        for (word, &grown) in self.words[y * self.stride..(y + 1) * self.stride]
+            .iter_mut()
+            .zip(buffer.iter())
+        {
+            *word |= grown;
+        }
+        true
+    }
+}
+
+/// Fills the runs of `open` bits which `row` touches. Bits of `row` which are not open
+/// spread only to their adjacent open bits.
+fn fill_runs(row: &mut [u64], open: &[u64]) {
+    // Fill east and then west, carrying the fill over word boundaries.
+    let mut carry = 0;
+    for (word, &open) in row.iter_mut().zip(open) {
+        *word = fill_east(*word | carry & open, open);
+        carry = *word >> (WORD_BITS - 1);
+    }
+    let mut carry = 0;
+    for (word, &open) in row.iter_mut().zip(open).rev() {
+        *word = fill_west(*word | carry & open, open);
+        carry = *word << (WORD_BITS - 1);
+    }
+}
+
+/// Fills the bits of `seeds` east, to higher bits, through the bits of `open` in log steps.
+#[inline]
+fn fill_east(mut seeds: u64, mut open: u64) -> u64 {
+    for shift in [1, 2, 4, 8, 16, 32] {
+        seeds |= open & seeds << shift;
+        open &= open << shift;
+    }
+    seeds
+}
+
+/// Fills the bits of `seeds` west, to lower bits, through the bits of `open` in log steps.
+#[inline]
+fn fill_west(mut seeds: u64, mut open: u64) -> u64 {
+    for shift in [1, 2, 4, 8, 16, 32] {
+        seeds |= open & seeds >> shift;
+        open &= open >> shift;
+    }
+    seeds
+}
+
+/// Returns word `i` of the row with every bit moved one tile east, i.e. to higher x.
+#[inline]
+fn east(row: &[u64], i: usize) -> u64 {
+    let carry = if i > 0 {
+        row[i - 1] >> (WORD_BITS - 1)
+    } else {
+        0
+    };
+    row[i] << 1 | carry
+}
+
+/// Returns word `i` of the row with every bit moved one tile west, i.e. to lower x.
+#[inline]
+fn west(row: &[u64], i: usize) -> u64 {
+    let carry = row.get(i + 1).map_or(0, |next| next << (WORD_BITS - 1));
+    row[i] >> 1 | carry
+}
+
+/// Returns word `i` of the row with the bits and both of their horizontal neighbors set.
+/// Empty rows, outside the grid, have no bits.
+#[inline]
+fn spread(row: &[u64], i: usize) -> u64 {
+    if row.is_empty() {
+        return 0;
+    }
+    row[i] | east(row, i) | west(row, i)
+}
+
+/// Writes the bits of the items accepted by `f` into the words of a row, the first item in
+/// the lowest bit of the first word.
+pub fn pack_bits<T>(items: &[T], f: impl Fn(&T) -> bool, row: &mut [u64]) {
+    for (word, items) in row.iter_mut().zip(items.chunks(WORD_BITS)) {
+        *word = items.chunks(8).enumerate().fold(0, |word, (byte, items)| {
+            word | pack_byte(items, &f) << (8 * byte)
+        });
+    }
+}
+
+/// Returns the bits of up to eight items accepted by `f`, the first item in the lowest bit.
+#[inline]
+fn pack_byte<T>(items: &[T], f: impl Fn(&T) -> bool) -> u64 {
+    let mut bytes = [0; 8];
+    for (byte, item) in bytes.iter_mut().zip(items) {
+        *byte = f(item) as u8;
+    }
+    // Multiplying gathers the lowest bit of each byte into the highest byte.
+    (u64::from_le_bytes(bytes).wrapping_mul(0x0102_0408_1020_4080)) >> 56
+}
+
+/// Bits of each byte spread to the lowest bits of the bytes of a word, so that bit `k` of
+/// the index is bit `0` of byte `k`.
+const SPREAD_BITS: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut bit = 0;
+        while bit < 8 {
+            table[i] |= ((i as u64 >> bit) & 1) << (8 * bit);
+            bit += 1;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Adds one to the bit-sliced counts of the bits set in `word`.
+#[inline]
+fn add_to_planes(planes: &mut [u64; 4], word: u64) {
+    let mut carry = word;
+    for plane in planes.iter_mut() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *plane ^ carry;
+        carry &= *plane;
+        *plane = sum;
+    }
+}
+
+/// Iterator over the indexes of the set bits of a word, from the lowest.
+struct BitIter(u64);
+
+impl Iterator for BitIter {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let bit = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::BitGrid, position::Position};
+
+    /// Pseudo-random bits spread over the words of wide rows.
+    fn bits(width: usize, height: usize, density: u64) -> BitGrid {
+        let bits: Vec<_> = (0..(width * height) as u64)
+            .map(|i| i * 2654435761 % 100 < density)
+            .collect();
+        BitGrid::from_slice(width, &bits, |&bit| bit)
+    }
+
+    #[test]
+    fn counts_match_neighbors() {
+        for (width, height) in [(1, 1), (7, 3), (64, 2), (65, 4), (130, 5)] {
+            let grid = bits(width, height, 30);
+            let counts = grid.neighbor_counts();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let expected = Position::new(x, y)
+                        .neighbors(width, height)
+                        .filter(|&pos| grid.contains(pos))
+                        .count();
+                    assert_eq!(counts[y * width + x] as usize, expected, "({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dilate_stays_inside_grid() {
+        let mut grid = BitGrid::new(65, 2);
+        grid.insert(Position::new(64, 0));
+        grid.insert(Position::new(0, 1));
+
+        let dilated: Vec<_> = grid.dilate().iter().collect();
+        assert_eq!(
+            dilated,
+            [
+                Position::new(0, 0),
+                Position::new(1, 0),
+                Position::new(63, 0),
+                Position::new(64, 0),
+                Position::new(0, 1),
+                Position::new(1, 1),
+                Position::new(63, 1),
+                Position::new(64, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn flood_fill_matches_search() {
+        for (width, height) in [(9, 9), (70, 20), (129, 7)] {
+            let passable = bits(width, height, 60);
+            let start = (0..width * height)
+                .map(|i| Position::from_index(i, width))
+                .find(|&pos| passable.contains(pos))
+                .unwrap();
+
+            let mut expected = BitGrid::new(width, height);
+            let mut stack = vec![start];
+            expected.insert(start);
+            while let Some(pos) = stack.pop() {
+                for n in pos.neighbors(width, height) {
+                    if passable.contains(n) && !expected.contains(n) {
+                        expected.insert(n);
+                        stack.push(n);
+                    }
+                }
+            }
+
+            let mut seed = BitGrid::new(width, height);
+            seed.insert(start);
+            assert_eq!(seed.flood_fill(&passable), expected);
+        }
+    }
+}
@@ -1,11 +1,23 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use rand::Rng;
+
 use crate::{
     area::{Area, MineCount},
+    bitboard::{self, BitGrid},
+    history::TileChange,
     position::Position,
+    probability, solver,
     tile::{State, Tile, Value},
+    topology::{Grid, Lattice, Mask, Shape, Topology},
 };
 
 /// [`Seed`] is a seed used for stable generation of a board.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Seed(u64);
 
 impl Seed {
@@ -28,17 +40,76 @@ impl Seed {
     }
 }
 
+/// [`StartPolicy`] determines which tiles around [`GenerationSettings::start_pos`] are kept
+/// free of mines during board generation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StartPolicy {
+    /// Mines can be placed anywhere, including the start position.
+    Unprotected,
+    /// Start position is never a mine.
+    #[default]
+    SafeTile,
+    /// Start position and all of its neighbors are never mines, so the first
+    /// opened tile is always empty.
+    SafeNeighborhood,
+}
+
+/// [`GenerationMode`] determines which kind of boards are accepted during generation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GenerationMode {
+    /// Any random board is accepted.
+    #[default]
+    Random,
+    /// Only boards which can be solved from [`GenerationSettings::start_pos`] by deduction
+    /// alone, without guessing, are accepted. Candidate boards are generated one after
+    /// another until one passes or `max_attempts` boards have been rejected.
+    NoGuess { max_attempts: usize },
+}
+
 /// [`GenerationSettings`] contains parameters for generating a [`Board`], including [`Seed`].
-/// Two boards with same settings are exactly the same.
+/// Two boards with same settings are exactly the same. Width, height and shape describe the
+/// [`Grid`] of the board, and are ignored by [`Board::try_with_topology`], which generates
+/// boards on other topologies such as [`Grid::masked`].
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenerationSettings {
     pub seed: Seed,
-    // TODO: limit width and height to non-zero values.
     pub width: usize,
     pub height: usize,
     pub mine_count: usize,
-    // TODO: use start_pos in board generation.
     pub start_pos: Position,
+    pub start_policy: StartPolicy,
+    pub mode: GenerationMode,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shape: Shape,
+    /// Largest number of mines in a single cell, from 1 to [`Tile::MAX_MINES`]. With more
+    /// than one, every mine is placed in a random cell which isn't full yet and numbers
+    /// count all mines around them.
+    #[cfg_attr(feature = "serde", serde(default = "one_mine_per_cell"))]
+    pub mines_per_cell: u8,
+}
+
+#[cfg(feature = "serde")]
+fn one_mine_per_cell() -> u8 {
+    1
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        Self {
+            seed: Seed::default(),
+            width: 0,
+            height: 0,
+            mine_count: 0,
+            start_pos: Position::default(),
+            start_policy: StartPolicy::default(),
+            mode: GenerationMode::default(),
+            shape: Shape::default(),
+            mines_per_cell: 1,
+        }
+    }
 }
 
 impl GenerationSettings {
@@ -54,180 +125,1725 @@ impl GenerationSettings {
     ///     height: 8,
     ///     mine_count: 10,
     ///     start_pos: (5, 5).into(),
+    ///     ..Default::default()
     /// };
     /// assert_eq!(gs.to_state_seed(), 774763251130295452938);
     /// ```
-    pub fn to_state_seed(self) -> u128 {
+    pub fn to_state_seed(&self) -> u128 {
+        self.state_seed_for(self.width, self.height)
+    }
+
+    /// Converts [`GenerationSettings`] to seed like [`GenerationSettings::to_state_seed`],
+    /// using the given width and height instead of the ones in the settings.
+    fn state_seed_for(&self, width: usize, height: usize) -> u128 {
         ((self.seed.get() as u128) << 64)
-            ^ (self.width as u128) << 32
-            ^ (self.height as u128) << 24
+            ^ (width as u128) << 32
+            ^ (height as u128) << 24
             ^ (self.start_pos.x as u128) << 16
             ^ (self.start_pos.y as u128) << 8
             ^ (self.mine_count as u128)
     }
+
+    /// Returns indexes of the tiles which must stay free of mines according to
+    /// [`StartPolicy`], in ascending order. Positions outside the board are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{GenerationSettings, StartPolicy};
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 4,
+    ///     height: 4,
+    ///     start_pos: (0, 1).into(),
+    ///     start_policy: StartPolicy::SafeNeighborhood,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(gs.safe_indexes(), [0, 1, 4, 5, 8, 9]);
+    /// ```
+    pub fn safe_indexes(&self) -> Vec<usize> {
+        self.safe_indexes_in(&self.grid())
+    }
+
+    /// Returns indexes of the cells of `topology` which must stay free of mines, like
+    /// [`GenerationSettings::safe_indexes`].
+    fn safe_indexes_in<T: Topology>(&self, topology: &T) -> Vec<usize> {
+        let start = self.start_pos;
+        let Some(start_idx) = topology.to_index(start) else {
+            return Vec::new();
+        };
+
+        let mut idxs: Vec<usize> = match self.start_policy {
+            StartPolicy::Unprotected => Vec::new(),
+            StartPolicy::SafeTile => vec![start_idx],
+            StartPolicy::SafeNeighborhood => topology
+                .neighbors(start)
+                .filter_map(|pos| topology.to_index(pos))
+                .chain(std::iter::once(start_idx))
+                .collect(),
+        };
+        idxs.sort_unstable();
+        idxs
+    }
+
+    /// Returns the [`Grid`] of the board described by the settings.
+    #[inline]
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.width, self.height, self.shape)
+    }
+
+    /// Returns the [`Grid`] of the board described by the settings with only the cells of
+    /// `mask`, if given. Width and height of the mask replace the ones of the settings.
+    pub fn grid_with_mask(&self, mask: Option<Mask>) -> Grid {
+        match mask {
+            Some(mask) => Grid::masked(mask, self.shape),
+            None => self.grid(),
+        }
+    }
+
+    /// Checks that a [`Board`] can be generated with these settings. Board must have non-zero
+    /// width and height which don't overflow when multiplied, start position must be on the
+    /// board and mines must fit outside the tiles protected by [`StartPolicy`], leaving at
+    /// least one tile without a mine. Each tile can hold up to [`GenerationSettings::mines_per_cell`] mines, and
+    /// no tile can have more than [`Tile::MAX_NUMBER`] mines around it.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{BoardError, GenerationSettings};
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 10,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(gs.validate(), Ok(()));
+    ///
+    /// let crowded = GenerationSettings { mine_count: 64, ..gs };
+    /// assert_eq!(
+    ///     crowded.validate(),
+    ///     Err(BoardError::TooManyMines { mine_count: 64, max: 63 }),
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), BoardError> {
+        let (width, height) = (self.width, self.height);
+        if width == 0 || height == 0 {
+            return Err(BoardError::ZeroDimension { width, height });
+        }
+        if width.checked_mul(height).is_none() {
+            return Err(BoardError::SizeOverflow { width, height });
+        }
+        self.validate_in(&self.grid())
+    }
+
+    /// Checks that a board can be generated on `topology` with these settings, like
+    /// [`GenerationSettings::validate`]. Width, height and shape of the settings are
+    /// ignored, as by [`Board::try_with_topology`].
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{BoardError, GenerationSettings};
+    /// use mineraker::topology::{Grid, Mask, Shape};
+    ///
+    /// let gs = GenerationSettings {
+    ///     mine_count: 3,
+    ///     ..Default::default()
+    /// };
+    /// let ring: Mask = "###\n#.#\n###\n".parse().unwrap();
+    /// assert_eq!(gs.validate_in(&Grid::masked(ring, Shape::Bounded)), Ok(()));
+    ///
+    /// let empty = Mask::from_fn(3, 3, |_| false);
+    /// assert_eq!(
+    ///     gs.validate_in(&Grid::masked(empty, Shape::Bounded)),
+    ///     Err(BoardError::EmptyMask),
+    /// );
+    /// ```
+    pub fn validate_in<T: Topology>(&self, topology: &T) -> Result<(), BoardError> {
+        if !(1..=Tile::MAX_MINES).contains(&self.mines_per_cell) {
+            return Err(BoardError::MinesPerCell(self.mines_per_cell));
+        }
+        let size = topology.cell_count();
+        if size == 0 {
+            let (width, height) = (topology.width(), topology.height());
+            if width == 0 || height == 0 {
+                return Err(BoardError::ZeroDimension { width, height });
+            }
+            return Err(BoardError::EmptyMask);
+        }
+        if !topology.contains(self.start_pos) {
+            return Err(BoardError::StartOutOfBounds(self.start_pos));
+        }
+
+        // At least one tile has to be left without a mine, even if nothing is protected.
+        let max = (size - self.safe_indexes_in(topology).len()).min(size - 1)
+            * self.mines_per_cell as usize;
+        if self.mine_count > max {
+            return Err(BoardError::TooManyMines {
+                mine_count: self.mine_count,
+                max,
+            });
+        }
+
+        // Numbers above `Tile::MAX_NUMBER` would overflow into the mine values.
+        let max_number =
+            (topology.max_neighbors() * self.mines_per_cell as usize).min(self.mine_count);
+        if max_number > Tile::MAX_NUMBER as usize {
+            return Err(BoardError::NumberOverflow(max_number));
+        }
+
+        Ok(())
+    }
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct Board {
+/// [`BoardError`] describes why a [`Board`] could not be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardError {
+    /// Width or height of the board is zero.
+    ZeroDimension { width: usize, height: usize },
+    /// Number of tiles, `width * height`, doesn't fit in [`usize`].
+    SizeOverflow { width: usize, height: usize },
+    /// Topology has positions but no cells, such as a [`Grid`] with an empty [`Mask`].
+    EmptyMask,
+    /// Mine count is larger than the number of mines which fit in the tiles available for
+    /// mines.
+    TooManyMines { mine_count: usize, max: usize },
+    /// [`GenerationSettings::mines_per_cell`] is zero or more than [`Tile::MAX_MINES`].
+    MinesPerCell(u8),
+    /// Tiles can have more mines around them than [`Tile::MAX_NUMBER`], such as on
+    /// lattices of four or more dimensions.
+    NumberOverflow(usize),
+    /// Start position is outside the board.
+    StartOutOfBounds(Position),
+    /// No acceptable board was found within the given number of attempts.
+    AttemptsExhausted { attempts: usize },
+}
+
+impl std::fmt::Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::ZeroDimension { width, height } => {
+                write!(f, "board dimensions {}x{} must be non-zero", width, height)
+            }
+            BoardError::SizeOverflow { width, height } => write!(
+                f,
+                "board dimensions {}x{} overflow the number of tiles",
+                width, height
+            ),
+            BoardError::EmptyMask => write!(f, "mask has no cells"),
+            BoardError::TooManyMines { mine_count, max } => write!(
+                f,
+                "mine count {} is too large, at most {} mines fit on the board",
+                mine_count, max
+            ),
+            BoardError::MinesPerCell(mines) => write!(
+                f,
+                "{} mines per cell is not supported, expected 1 to {}",
+                mines,
+                Tile::MAX_MINES
+            ),
+            BoardError::NumberOverflow(number) => write!(
+                f,
+                "tiles can have {} mines around them, at most {} are supported",
+                number,
+                Tile::MAX_NUMBER
+            ),
+            BoardError::StartOutOfBounds(pos) => write!(
+                f,
+                "start position ({}, {}) is outside the board",
+                pos.x, pos.y
+            ),
+            BoardError::AttemptsExhausted { attempts } => {
+                write!(f, "no acceptable board was found in {} attempts", attempts)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+/// [`Chord`] describes the tiles opened by [`Board::chord`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Chord {
+    /// Positions of all opened tiles, including tiles opened by cascades.
+    pub opened: Vec<Position>,
+    /// Positions of opened mines, which are left unflagged when a flag was wrong.
+    pub mines_hit: Vec<Position>,
+}
+
+/// [`Board`] stores the tiles of a game in the order of the cells of its [`Topology`],
+/// which is a [`Grid`] by default.
+#[derive(Debug, Clone)]
+pub struct Board<T = Grid> {
     tiles: Vec<Tile>,
-    width: usize,
+    topology: T,
+    mines_per_cell: u8,
+}
+
+impl<T: Default> Default for Board<T> {
+    fn default() -> Self {
+        Self {
+            tiles: Vec::new(),
+            topology: T::default(),
+            mines_per_cell: 1,
+        }
+    }
 }
 
 impl Board {
-    /// Generates a new board with the given width, height, mine count and seed.
+    /// Generates a new board with the given width, height, mine count and seed. Tiles
+    /// protected by [`GenerationSettings::start_policy`] never contain mines.
     ///
     /// # Panics
-    /// If `mines >= width * height`.
+    /// If settings are invalid, see [`GenerationSettings::validate`]. Use [`Board::try_new`]
+    /// to handle invalid settings without panicking.
     pub fn new(settings: &GenerationSettings) -> Self {
-        let size = settings.width * settings.height;
-        assert!(
-            settings.mine_count < size,
-            "`mines` must be less than `size`"
-        );
+        Self::try_new(settings).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        // Generate mine indexes using config seed.
-        let mut rng = rand_pcg::Pcg64Mcg::new(settings.to_state_seed());
-        let mine_idxs = rand::seq::index::sample(&mut rng, size, settings.mine_count);
+    /// Generates a new board like [`Board::new`], but returns an error on invalid settings
+    /// instead of panicking.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, BoardError, GenerationSettings};
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 10,
+    ///     ..Default::default()
+    /// };
+    /// assert!(Board::try_new(&gs).is_ok());
+    ///
+    /// let flat = GenerationSettings { height: 0, ..gs };
+    /// assert_eq!(
+    ///     Board::try_new(&flat).unwrap_err(),
+    ///     BoardError::ZeroDimension { width: 8, height: 0 },
+    /// );
+    /// ```
+    pub fn try_new(settings: &GenerationSettings) -> Result<Self, BoardError> {
+        settings.validate()?;
+        Self::try_with_topology(settings.grid(), settings)
+    }
 
-        // Setup empty board with the final size.
-        let mut board = Self {
-            tiles: vec![Tile::default(); size],
-            width: settings.width,
-        };
+    /// Generates a board where the tile at the given position is [`Value::Near(0)`], so
+    /// opening it always cascades. Uses generation config with `pos` as the start position
+    /// and [`StartPolicy::SafeNeighborhood`], which keeps the result deterministic.
+    ///
+    /// # Errors
+    /// If settings are invalid with `pos` as the start position, see
+    /// [`GenerationSettings::validate`].
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, BoardError, GenerationSettings};
+    /// use mineraker::position::Position;
+    /// use mineraker::tile::Value;
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 20,
+    ///     ..Default::default()
+    /// };
+    /// let board = Board::with_empty_at(&gs, Position::new(3, 3)).unwrap();
+    /// assert_eq!(board.get_tile(Position::new(3, 3)).unwrap().value(), Value::Near(0));
+    ///
+    /// let dense = GenerationSettings { mine_count: 56, ..gs };
+    /// assert_eq!(
+    ///     Board::with_empty_at(&dense, Position::new(3, 3)).unwrap_err(),
+    ///     BoardError::TooManyMines { mine_count: 56, max: 55 },
+    /// );
+    /// ```
+    pub fn with_empty_at(settings: &GenerationSettings, pos: Position) -> Result<Self, BoardError> {
+        Self::try_new(&GenerationSettings {
+            start_pos: pos,
+            start_policy: StartPolicy::SafeNeighborhood,
+            ..*settings
+        })
+    }
+}
 
-        // Add mines and number tiles based on mine positions.
-        mine_idxs.iter().for_each(|idx| {
-            board.tiles[idx] = Tile::with_value(Value::Mine);
-            // Increment number of all non-mine neighbors.
-            Position::from_index(idx, settings.width)
-                .neighbors(settings.width, settings.height)
-                .for_each(|pos| {
-                    // Unwrap as these positions are directly from enumeration.
-                    board.get_tile_mut(pos).unwrap().increment_value();
-                });
-        });
+impl<T: Topology> Board<T> {
+    /// Generates a new board on the given topology like [`Board::try_new`]. Width, height
+    /// and shape of the settings are ignored.
+    ///
+    /// # Errors
+    /// If the topology has no cells, the start position is not a cell or the mines don't
+    /// fit, see [`GenerationSettings::validate`].
+    pub fn try_with_topology(
+        topology: T,
+        settings: &GenerationSettings,
+    ) -> Result<Self, BoardError> {
+        settings.validate_in(&topology)?;
+
+        // Boards are seeded with the size of their topology, which is the size in the
+        // settings for grids.
+        let seed = settings.state_seed_for(topology.width(), topology.height());
+        let mut rng = rand_pcg::Pcg64Mcg::new(seed);
+        match settings.mode {
+            GenerationMode::Random => Ok(Self::generate(topology, settings, &mut rng)),
+            // Candidates are drawn from the same random stream, so the first candidate is
+            // the same board as generated in random mode.
+            GenerationMode::NoGuess { max_attempts } => (0..max_attempts)
+                .map(|_| Self::generate(topology.clone(), settings, &mut rng))
+                .find(|board| solver::is_solvable(board, settings.start_pos))
+                .ok_or(BoardError::AttemptsExhausted {
+                    attempts: max_attempts,
+                }),
+        }
+    }
+
+    /// Creates a board from tiles in the order of the cells of `topology`. Length of
+    /// `tiles` must be the number of cells. Cells of the board hold as many mines as the
+    /// fullest tile, and at least one.
+    pub(crate) fn from_tiles(tiles: Vec<Tile>, topology: T) -> Self {
+        debug_assert_eq!(tiles.len(), topology.cell_count());
+        let mines_per_cell = tiles.iter().map(Tile::mines).max().unwrap_or(0).max(1);
+        Self {
+            tiles,
+            topology,
+            mines_per_cell,
+        }
+    }
 
+    /// Generates a single random board using validated settings.
+    fn generate(topology: T, settings: &GenerationSettings, rng: &mut rand_pcg::Pcg64Mcg) -> Self {
+        let size = topology.cell_count();
+        let mut safe_idxs = settings.safe_indexes_in(&topology);
+        let per_cell = settings.mines_per_cell as usize;
+        // With several mines per cell, at least as many mines as cells could be spread over
+        // every cell, so a random cell is kept free if none is protected.
+        if safe_idxs.is_empty() && settings.mine_count >= size {
+            safe_idxs.push(rng.gen_range(0..size));
+        }
+
+        // Generate mine indexes from the unprotected tiles only and then shift them over
+        // the protected ones. Every tile has a slot for each mine it can hold, so with one
+        // mine per cell slots are tiles.
+        let slots = (size - safe_idxs.len()) * per_cell;
+        let mine_idxs = rand::seq::index::sample(rng, slots, settings.mine_count)
+            .into_iter()
+            .map(|slot| {
+                safe_idxs.iter().fold(
+                    slot / per_cell,
+                    |idx, &safe| if safe <= idx { idx + 1 } else { idx },
+                )
+            });
+
+        let mut board = Self::from_mines(topology, mine_idxs);
+        board.mines_per_cell = settings.mines_per_cell;
         board
     }
 
-    /// Generates a boad with empty tiles at the given position, using generation config.
-    #[allow(dead_code)]
-    pub fn with_empty_at(_settings: &GenerationSettings, _pos: Position) -> Self {
-        todo!()
+    /// Creates a board with all tiles closed and mines at the given indexes. Indexes
+    /// given several times hold as many mines.
+    pub(crate) fn from_mines(topology: T, mine_idxs: impl IntoIterator<Item = usize>) -> Self {
+        let mine_idxs: Vec<usize> = mine_idxs.into_iter().collect();
+
+        // Numbers of bounded rectangles with one mine per cell are counted for all tiles
+        // at once from the mine bits. Mines are placed over their counts.
+        let counts = if let Some(mines) = Self::mine_bits(&topology, &mine_idxs) {
+            mines.neighbor_counts()
+        } else {
+            let mut counts = vec![0; topology.cell_count()];
+            for &idx in &mine_idxs {
+                for n in topology.neighbors(topology.to_position(idx)) {
+                    counts[Self::cell_index(&topology, n)] += 1;
+                }
+            }
+            counts
+        };
+        let mut tiles: Vec<Tile> = counts
+            .into_iter()
+            .map(|count| Tile::with_value(Value::Near(count)))
+            .collect();
+        for idx in mine_idxs {
+            tiles[idx] = Tile::with_mines(tiles[idx].mines() + 1);
+        }
+
+        Self::from_tiles(tiles, topology)
+    }
+
+    /// Returns the mines as bits if `topology` is a bounded rectangle and no cell holds
+    /// more than one mine.
+    fn mine_bits(topology: &T, mine_idxs: &[usize]) -> Option<BitGrid> {
+        if !topology.is_bounded_rectangle() {
+            return None;
+        }
+        let mut mines = BitGrid::new(topology.width(), topology.height());
+        for &idx in mine_idxs {
+            let pos = topology.to_position(idx);
+            if mines.contains(pos) {
+                return None;
+            }
+            mines.insert(pos);
+        }
+        Some(mines)
+    }
+
+    /// Returns the index of a position which is known to be a cell, such as a neighbor.
+    #[inline]
+    fn cell_index(topology: &T, pos: Position) -> usize {
+        topology
+            .to_index(pos)
+            .expect("neighbors of a cell should be cells")
+    }
+
+    /// Returns the empty area connected to `pos` together with its neighbors. Areas of
+    /// bounded rectangles are filled bit-parallel, see [`BitGrid::flood_fill_by`].
+    fn empty_area_border(&self, pos: Position) -> BitGrid {
+        let width = self.width();
+        let mut border = BitGrid::new(width, self.height());
+        if !self.topology.is_bounded_rectangle() {
+            for p in self.flood_area(pos, |_, &tile| tile == Tile::EMPTY_CLOSED) {
+                border.insert(p);
+                for n in self.neighbors(p) {
+                    border.insert(n);
+                }
+            }
+            return border;
+        }
+
+        border.insert(pos);
+        border
+            .flood_fill_by(|y, row| {
+                let tiles = &self.tiles[y * width..(y + 1) * width];
+                bitboard::pack_bits(tiles, |&tile| tile == Tile::EMPTY_CLOSED, row);
+            })
+            .dilate()
     }
 
-    fn empty_area(&self, pos: Position) -> Vec<Position> {
+    /// Returns the area of tiles connected to `pos` through neighbors accepted by
+    /// `is_inside`. The area always contains `pos`, even if it is not accepted.
+    pub(crate) fn flood_area(
+        &self,
+        pos: Position,
+        mut is_inside: impl FnMut(Position, &Tile) -> bool,
+    ) -> Vec<Position> {
         let mut stack = Vec::new();
-        let mut emptys = Vec::new();
+        let mut area = Vec::new();
         let mut processed = vec![false; self.tiles.len()];
 
+        processed[Self::cell_index(&self.topology, pos)] = true;
         stack.push(pos);
         while let Some(p) = stack.pop() {
-            emptys.push(p);
+            area.push(p);
 
-            stack.extend(p.neighbors(self.width, self.height()).filter(|p| {
-                let i = p.to_index(self.width);
-                let unprocessed = !processed[i] && self.tiles[i] == Tile::EMPTY_CLOSED;
+            stack.extend(self.neighbors(p).filter(|&p| {
+                let i = Self::cell_index(&self.topology, p);
+                let unprocessed = !processed[i] && is_inside(p, &self.tiles[i]);
                 processed[i] = true;
 
                 unprocessed
             }));
         }
 
-        emptys
+        area
     }
 
-    pub fn open_from(&mut self, pos: Position) {
-        if let Some(tile) = self.get_tile_mut(pos) {
-            tile.open();
+    /// Opens the tile at the given position if it is closed. If the tile is empty, the
+    /// whole empty area around it is opened along with the numbers bordering the area.
+    /// Returns positions of all opened tiles, starting with the given position and
+    /// followed by the cascade in row-major order.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    /// use mineraker::position::Position;
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 10,
+    ///     ..Default::default()
+    /// };
+    /// let mut board = Board::with_empty_at(&gs, Position::new(4, 4)).unwrap();
+    ///
+    /// // Empty tile opens at least itself and all of its neighbors.
+    /// assert!(board.open_from(Position::new(4, 4)).len() >= 9);
+    /// // Opened tiles can't be opened again.
+    /// assert!(board.open_from(Position::new(4, 4)).is_empty());
+    /// ```
+    pub fn open_from(&mut self, pos: Position) -> Vec<Position> {
+        let mut opened = Vec::new();
+        if !self.open_tile(pos) {
+            return opened;
         }
-        if let Some(tile) = self.get_tile(pos) {
-            if tile.value() == Value::Near(0) {
-                for p in self.empty_area(pos) {
-                    self.tiles[p.to_index(self.width)].open();
-                    p.neighbors(self.width, self.height())
-                        .for_each(|p| self.tiles[p.to_index(self.width)].open());
+        opened.push(pos);
+
+        if self.get_tile(pos).unwrap().value() == Value::Near(0) {
+            for p in self.empty_area_border(pos).iter() {
+                if self.open_tile(p) {
+                    opened.push(p);
                 }
             }
         }
+
+        opened
+    }
+
+    /// Opens all closed neighbors of an open number tile, if the number of flags around
+    /// the tile equals its number. Empty neighbors cascade like in [`Board::open_from`].
+    /// Nothing is opened if the tile isn't an open number or the flag count doesn't match.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    /// use mineraker::position::Position;
+    /// use mineraker::tile::{State, Value};
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 4,
+    ///     height: 1,
+    ///     mine_count: 1,
+    ///     ..Default::default()
+    /// };
+    /// let mut board = Board::new(&gs);
+    /// let mine = (0..4)
+    ///     .map(|x| Position::new(x, 0))
+    ///     .find(|&pos| board.get_tile(pos).unwrap().value() == Value::Mine)
+    ///     .unwrap();
+    /// let number = Position::new(if mine.x == 0 { 1 } else { mine.x - 1 }, 0);
+    ///
+    /// board.open_from(number);
+    /// // Without a flag around the number, chord does nothing.
+    /// assert!(board.chord(number).opened.is_empty());
+    ///
+    /// board.flag_from(mine);
+    /// let chord = board.chord(number);
+    /// assert!(chord.mines_hit.is_empty());
+    /// assert!(board
+    ///     .neighbors_tile_and_pos(number)
+    ///     .all(|(_, tile)| tile.state() != State::Closed));
+    /// ```
+    pub fn chord(&mut self, pos: Position) -> Chord {
+        let mut chord = Chord::default();
+        let number = match self.get_tile(pos) {
+            Some(tile) if tile.state() == State::Open => match tile.value() {
+                Value::Near(n) => n as usize,
+                Value::Mine => return chord,
+            },
+            _ => return chord,
+        };
+        let flags: usize = self
+            .neighbors_tile_and_pos(pos)
+            .map(|(_, tile)| tile.flags() as usize)
+            .sum();
+        if flags != number {
+            return chord;
+        }
+
+        let neighbors: Vec<_> = self.neighbors(pos).collect();
+        for n in neighbors {
+            chord.opened.extend(self.open_from(n));
+        }
+        chord.mines_hit = chord
+            .opened
+            .iter()
+            .copied()
+            .filter(|&p| self.get_tile(p).unwrap().value() == Value::Mine)
+            .collect();
+
+        chord
+    }
+
+    /// Sets the changed tiles to their state after the change.
+    pub fn apply_changes(&mut self, changes: &[TileChange]) {
+        for change in changes {
+            if let Some(tile) = self.get_tile_mut(change.pos) {
+                *tile = change.after;
+            }
+        }
+    }
+
+    /// Sets the changed tiles back to their state before the change, in reverse order.
+    pub fn revert_changes(&mut self, changes: &[TileChange]) {
+        for change in changes.iter().rev() {
+            if let Some(tile) = self.get_tile_mut(change.pos) {
+                *tile = change.before;
+            }
+        }
     }
 
     /// Opens single tile if the given position is within board bounds and
-    /// tile is valid as openable i.e. it is closed.
+    /// tile is valid as openable i.e. it is closed. Returns `true` if the tile was opened.
     #[inline]
-    fn open_tile(&mut self, pos: Position) {
-        if let Some(tile) = self.get_tile_mut(pos) {
-            tile.open();
+    fn open_tile(&mut self, pos: Position) -> bool {
+        match self.get_tile_mut(pos) {
+            Some(tile) if tile.state() == State::Closed => {
+                tile.open();
+                true
+            }
+            _ => false,
         }
     }
 
+    /// Toggles flag of the tile at the given position. Returns `true` if the tile changed,
+    /// i.e. it was closed or flagged. On boards with several mines per cell, flags are
+    /// counted up to [`Board::mines_per_cell`] before the tile is closed again, see
+    /// [`Tile::cycle_flag`].
     #[inline]
-    pub fn flag_from(&mut self, pos: Position) {
-        if let Some(tile) = self.get_tile_mut(pos) {
-            tile.toggle_flag();
+    pub fn flag_from(&mut self, pos: Position) -> bool {
+        let max_flags = self.mines_per_cell;
+        match self.get_tile_mut(pos) {
+            Some(tile) if tile.state() != State::Open => {
+                tile.cycle_flag(max_flags);
+                true
+            }
+            _ => false,
         }
     }
 
     /// Returns tile's closed neighbor tiles as [`Area`] with mine count calculated from
-    /// the tile's [`Value`]. If mine count is not possible to calculate, (e.g. position is
-    /// out of bounds or tile at position is a mine) returns mine count as `0..=8`.
+    /// the tile's [`Value`] and the flags around it. If mine count is not possible to
+    /// calculate, (e.g. position is out of bounds or tile at position is a mine) returns
+    /// mine count as anything from zero to the number of mines the closed neighbors hold
+    /// when full.
     ///
     /// TODO: Add example.
-    fn tile_neighbors_area(&self, pos: Position) -> Area {
-        let flags_around = self
+    pub(crate) fn tile_neighbors_area(&self, pos: Position) -> Area {
+        let flags_around: usize = self
             .neighbors_tile_and_pos(pos)
-            .filter(|(_, tile)| tile.state() == State::Flag)
-            .count();
+            .map(|(_, tile)| tile.flags() as usize)
+            .sum();
+        let closed: HashSet<Position> = self
+            .neighbors_tile_and_pos(pos)
+            .filter_map(|(p, tile)| tile.state().eq(&State::Closed).then_some(p))
+            .collect();
+        let per_cell = self.mines_per_cell as usize;
+        let unknown = 0..=closed.len() * per_cell;
 
-        Area::new(
-            self.neighbors_tile_and_pos(pos)
-                .filter_map(|(p, tile)| tile.state().eq(&State::Closed).then(|| p))
-                .collect(),
+        Area::with_mines_per_position(
+            closed,
             self.get_tile(pos)
                 .map(|tile| match tile.value() {
-                    Value::Near(val) => MineCount::from(val as usize - flags_around),
-                    Value::Mine => MineCount::from(0..=8),
+                    Value::Near(val) => {
+                        MineCount::from((val as usize).saturating_sub(flags_around))
+                    }
+                    Value::Mine => MineCount::from(unknown.clone()),
                 })
-                .unwrap_or_else(|| MineCount::from(0..=8)),
+                .unwrap_or_else(|| MineCount::from(unknown)),
+            per_cell,
         )
     }
 
+    /// Returns iterator over the neighbor positions of `pos` according to the
+    /// [`Topology`] of the board.
+    #[inline]
+    pub fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        self.topology.neighbors(pos)
+    }
+
     pub fn neighbors_tile_and_pos(&self, pos: Position) -> impl Iterator<Item = (Position, &Tile)> {
-        pos.neighbors(self.width, self.height())
-            .map(|p| (p, self.get_tile(p).unwrap()))
+        self.neighbors(pos).map(|p| (p, self.get_tile(p).unwrap()))
+    }
+
+    /// Returns iterator over all tiles and their positions in row-major order.
+    pub fn tiles_and_pos(&self) -> impl Iterator<Item = (Position, &Tile)> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(idx, tile)| (self.topology.to_position(idx), tile))
+    }
+
+    /// Returns a displayable view of the board where every tile shows its value. Boards on
+    /// bounded grids without a mask and with one mine per cell can be parsed back from the
+    /// output into a board with all tiles closed. Other boards are only for display, as
+    /// numbers above 8 and stacked mines, which show as a single `*`, can't be parsed.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    ///
+    /// let board = Board::new(&GenerationSettings {
+    ///     width: 4,
+    ///     height: 3,
+    ///     mine_count: 2,
+    ///     ..Default::default()
+    /// });
+    /// let parsed: Board = board.solution().to_string().parse().unwrap();
+    ///
+    /// assert_eq!(parsed.to_string(), board.to_string());
+    /// assert_eq!(parsed.solution().to_string(), board.solution().to_string());
+    /// ```
+    #[inline]
+    pub fn solution(&self) -> Solution<'_, T> {
+        Solution(self)
+    }
+
+    /// Returns a displayable view of the board for [`Shape::Hex`], where tiles are
+    /// separated by spaces and odd rows are shifted right by half a tile. Tiles use the
+    /// characters of [`Display`] for [`Board`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    /// use mineraker::position::Position;
+    /// use mineraker::topology::Shape;
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 4,
+    ///     height: 3,
+    ///     mine_count: 0,
+    ///     shape: Shape::Hex,
+    ///     ..Default::default()
+    /// };
+    /// let mut board = Board::new(&gs);
+    /// board.flag_from(Position::new(3, 1));
+    ///
+    /// assert_eq!(board.offset_rows().to_string(), "# # # #\n # # # ?\n# # # #\n");
+    /// board.open_from(Position::new(0, 0));
+    /// assert_eq!(board.solution().offset_rows().to_string(), "_ _ _ _\n _ _ _ _\n_ _ _ _\n");
+    /// ```
+    #[inline]
+    pub fn offset_rows(&self) -> OffsetRows<'_, T> {
+        OffsetRows {
+            board: self,
+            solution: false,
+        }
+    }
+
+    /// Returns the probability of being a mine for every closed and flagged tile, as seen
+    /// by the player. See [`probability::mine_probabilities`].
+    #[inline]
+    pub fn mine_probabilities(&self) -> Option<HashMap<Position, f64>> {
+        probability::mine_probabilities(self)
+    }
+
+    /// Returns the total number of mines on the board, counting every mine of tiles which
+    /// hold several.
+    pub fn mine_count(&self) -> usize {
+        self.tiles.iter().map(|tile| tile.mines() as usize).sum()
+    }
+
+    /// Returns the largest number of mines a single cell of the board can hold, which is
+    /// [`GenerationSettings::mines_per_cell`] for generated boards. Flags on a tile are
+    /// counted up to this number.
+    #[inline]
+    pub fn mines_per_cell(&self) -> u8 {
+        self.mines_per_cell
+    }
+
+    /// Returns `true` if the given position is a tile of the board.
+    #[inline]
+    pub fn contains(&self, pos: Position) -> bool {
+        self.topology.contains(pos)
     }
 
     #[inline]
     pub fn get_tile_mut(&mut self, pos: Position) -> Option<&mut Tile> {
-        let idx = pos.to_index(self.width);
+        let idx = self.topology.to_index(pos)?;
         self.tiles.get_mut(idx)
     }
 
     #[inline]
     pub fn get_tile(&self, pos: Position) -> Option<&Tile> {
-        let idx = pos.to_index(self.width);
+        let idx = self.topology.to_index(pos)?;
         self.tiles.get(idx)
     }
 
+    /// Returns the width of the rectangle containing all tiles.
     #[inline]
     pub fn width(&self) -> usize {
-        self.width
+        self.topology.width()
     }
 
+    /// Returns the height of the rectangle containing all tiles.
     #[inline]
     pub fn height(&self) -> usize {
-        self.tiles.len().checked_div(self.width).unwrap_or_default()
+        self.topology.height()
+    }
+
+    #[inline]
+    pub fn topology(&self) -> &T {
+        &self.topology
+    }
+
+    /// Writes the given rows of the rectangle containing all tiles, with `tile` written for
+    /// every tile and a space for the positions which are not tiles. Tiles of odd rows
+    /// are preceded by `offset` and tiles are separated by `separator`.
+    fn write_rows(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        rows: Range<usize>,
+        offset: &str,
+        separator: &str,
+        tile: impl Fn(&mut std::fmt::Formatter<'_>, &Tile) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        for y in rows {
+            if y % 2 == 1 {
+                write!(f, "{}", offset)?;
+            }
+            for x in 0..self.width() {
+                if x > 0 {
+                    write!(f, "{}", separator)?;
+                }
+                match self.get_tile(Position { x, y }) {
+                    Some(t) => tile(f, t)?,
+                    None => write!(f, " ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Topology> std::fmt::Display for Board<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_rows(f, 0..self.height(), "", "", |f, tile| write!(f, "{}", tile))
+    }
+}
+
+/// Tiles of a [`Board`] encoded with [`Tile::to_byte`], serialized as bytes.
+#[cfg(feature = "serde")]
+struct TileBytes(Vec<u8>);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TileBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TileBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = TileBytes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "tile bytes")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<TileBytes, E> {
+                Ok(TileBytes(v.to_vec()))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<TileBytes, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(TileBytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// [`Board`] is serialized as the width of its [`Grid`], one byte per tile in the order of
+/// the cells, the [`Shape`] and [`Mask`] of the grid and the number of mines per cell.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let tiles = TileBytes(self.tiles.iter().map(|tile| tile.to_byte()).collect());
+        let mut state = serializer.serialize_struct("Board", 5)?;
+        state.serialize_field("width", &self.topology.width)?;
+        state.serialize_field("tiles", &tiles)?;
+        state.serialize_field("shape", &self.topology.shape)?;
+        state.serialize_field("mask", &self.topology.mask)?;
+        state.serialize_field("mines_per_cell", &self.mines_per_cell)?;
+        state.end()
+    }
+}
+
+/// Deserialized boards are checked to have valid dimensions and tiles, no more mines or
+/// flags in a tile than allowed per cell, and numbers which match the mines around them.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Board")]
+        struct Repr {
+            width: usize,
+            tiles: TileBytes,
+            #[serde(default)]
+            shape: Shape,
+            #[serde(default)]
+            mask: Option<Mask>,
+            #[serde(default = "one_mine_per_cell")]
+            mines_per_cell: u8,
+        }
+
+        let Repr {
+            width,
+            tiles,
+            shape,
+            mask,
+            mines_per_cell,
+        } = Repr::deserialize(deserializer)?;
+        if !(1..=Tile::MAX_MINES).contains(&mines_per_cell) {
+            return Err(D::Error::custom(BoardError::MinesPerCell(mines_per_cell)));
+        }
+        if let Some(mask) = &mask {
+            if mask.width() != width || mask.cell_count() != tiles.0.len() {
+                return Err(D::Error::custom(format!(
+                    "{} tiles of width {} don't match the {} cells of width {} in the mask",
+                    tiles.0.len(),
+                    width,
+                    mask.cell_count(),
+                    mask.width()
+                )));
+            }
+        } else if width == 0 || !tiles.0.len().is_multiple_of(width) {
+            return Err(D::Error::custom(format!(
+                "{} tiles don't fit in rows of width {}",
+                tiles.0.len(),
+                width
+            )));
+        }
+        let tiles = tiles
+            .0
+            .into_iter()
+            .map(|byte| {
+                Tile::from_byte(byte)
+                    .ok_or_else(|| D::Error::custom(format!("invalid tile byte {:#04x}", byte)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let grid = match mask {
+            Some(mask) => Grid::masked(mask, shape),
+            None => Grid::new(width, tiles.len() / width, shape),
+        };
+        let mut board = Self::from_tiles(tiles, grid);
+        if board.mines_per_cell > mines_per_cell
+            || board.tiles.iter().any(|tile| tile.flags() > mines_per_cell)
+        {
+            return Err(D::Error::custom(format!(
+                "tiles hold more mines or flags than the {} allowed per cell",
+                mines_per_cell
+            )));
+        }
+        board.mines_per_cell = mines_per_cell;
+        for (pos, tile) in board.tiles_and_pos() {
+            let mines: usize = board
+                .neighbors_tile_and_pos(pos)
+                .map(|(_, tile)| tile.mines() as usize)
+                .sum();
+            if let Value::Near(n) = tile.value() {
+                if n as usize != mines {
+                    return Err(D::Error::custom(format!(
+                        "tile at ({}, {}) shows {} but has {} mines around it",
+                        pos.x, pos.y, n, mines
+                    )));
+                }
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+/// [`Solution`] displays the values of all tiles of a [`Board`] regardless of their state.
+/// Created with [`Board::solution`].
+#[derive(Debug)]
+pub struct Solution<'a, T = Grid>(&'a Board<T>);
+
+impl<T> Clone for Solution<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Solution<'_, T> {}
+
+impl<'a, T> Solution<'a, T> {
+    /// Returns the solution laid out in offset rows like [`Board::offset_rows`].
+    #[inline]
+    pub fn offset_rows(self) -> OffsetRows<'a, T> {
+        OffsetRows {
+            board: self.0,
+            solution: true,
+        }
+    }
+}
+
+impl<T: Topology> std::fmt::Display for Solution<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.write_rows(f, 0..self.0.height(), "", "", |f, tile| {
+            write!(f, "{}", tile.value())
+        })
+    }
+}
+
+/// [`OffsetRows`] displays a [`Board`] with odd rows shifted right by half a tile, which
+/// lays out the tiles of a [`Shape::Hex`] board next to their neighbors. Created with
+/// [`Board::offset_rows`] and [`Solution::offset_rows`].
+#[derive(Debug)]
+pub struct OffsetRows<'a, T = Grid> {
+    board: &'a Board<T>,
+    solution: bool,
+}
+
+impl<T> Clone for OffsetRows<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for OffsetRows<'_, T> {}
+
+impl<T: Topology> std::fmt::Display for OffsetRows<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.board
+            .write_rows(f, 0..self.board.height(), " ", " ", |f, tile| {
+                match self.solution {
+                    true => write!(f, "{}", tile.value()),
+                    false => write!(f, "{}", tile),
+                }
+            })
+    }
+}
+
+impl<const N: usize> Board<Lattice<N>> {
+    /// Returns a displayable view of a board on a [`Lattice`], which shows one slice
+    /// through the first two dimensions at a time. Every slice starts with a line with
+    /// `slice` and the remaining coordinates of the slice, and slices are separated by an
+    /// empty line. Tiles use the characters of [`Display`] for [`Board`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    /// use mineraker::position::Point;
+    /// use mineraker::topology::Lattice;
+    ///
+    /// let cube = Lattice::new([3, 2, 2]);
+    /// let gs = GenerationSettings { mine_count: 0, ..Default::default() };
+    /// let mut board = Board::try_with_topology(cube, &gs).unwrap();
+    /// board.flag_from(cube.position(Point::new([2, 0, 1])));
+    ///
+    /// assert_eq!(board.slices().to_string(), "slice 0\n###\n###\n\nslice 1\n##?\n###\n");
+    /// board.open_from(cube.position(Point::new([0, 0, 0])));
+    /// assert!(board.solution().slices().to_string().ends_with("slice 1\n___\n___\n"));
+    /// ```
+    #[inline]
+    pub fn slices(&self) -> Slices<'_, N> {
+        Slices {
+            board: self,
+            solution: false,
+        }
+    }
+}
+
+impl<'a, const N: usize> Solution<'a, Lattice<N>> {
+    /// Returns the solution split into slices like [`Board::slices`].
+    #[inline]
+    pub fn slices(self) -> Slices<'a, N> {
+        Slices {
+            board: self.0,
+            solution: true,
+        }
     }
 }
 
-impl std::fmt::Display for Board {
+/// [`Slices`] displays a [`Board`] on a [`Lattice`] one slice through the first two
+/// dimensions at a time. Created with [`Board::slices`] and [`Solution::slices`].
+#[derive(Debug, Clone, Copy)]
+pub struct Slices<'a, const N: usize> {
+    board: &'a Board<Lattice<N>>,
+    solution: bool,
+}
+
+impl<const N: usize> std::fmt::Display for Slices<'_, N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..self.height() {
-            for x in 0..self.width {
-                write!(f, "{}", self.get_tile(Position { x, y }).unwrap())?;
+        let lattice = self.board.topology();
+        let rows = lattice.dims().get(1).copied().unwrap_or(1);
+        for slice in 0..lattice.slice_count() {
+            if slice > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "slice")?;
+            let corner = lattice.point(Position::new(0, slice * rows));
+            for c in corner.0.iter().skip(2) {
+                write!(f, " {}", c)?;
             }
             writeln!(f)?;
+            let range = slice * rows..(slice + 1) * rows;
+            self.board
+                .write_rows(f, range, "", "", |f, tile| match self.solution {
+                    true => write!(f, "{}", tile.value()),
+                    false => write!(f, "{}", tile),
+                })?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, BoardError, GenerationMode, GenerationSettings, Seed, StartPolicy},
+        position::Position,
+        solver,
+        tile::{State, Tile, Value},
+        topology::{Grid, Mask, Shape},
+    };
+
+    fn settings(seed: u64, start_policy: StartPolicy) -> GenerationSettings {
+        GenerationSettings {
+            seed: Seed::new(seed),
+            width: 9,
+            height: 9,
+            mine_count: 72,
+            start_pos: Position::new(4, 4),
+            start_policy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn start_tile_is_never_mine() {
+        for seed in 0..100 {
+            let board = Board::new(&settings(seed, StartPolicy::SafeTile));
+            let start = board.get_tile(Position::new(4, 4)).unwrap();
+
+            assert_ne!(start.value(), Value::Mine);
+        }
+    }
+
+    #[test]
+    fn start_neighborhood_is_never_mine() {
+        for seed in 0..100 {
+            let board = Board::new(&settings(seed, StartPolicy::SafeNeighborhood));
+            let start = board.get_tile(Position::new(4, 4)).unwrap();
+
+            assert_eq!(start.value(), Value::Near(0));
+            assert!(board
+                .neighbors_tile_and_pos(Position::new(4, 4))
+                .all(|(_, tile)| tile.value() != Value::Mine));
+        }
+    }
+
+    #[test]
+    fn protected_generation_is_deterministic() {
+        let gs = settings(7, StartPolicy::SafeNeighborhood);
+
+        assert_eq!(Board::new(&gs).tiles, Board::new(&gs).tiles);
+    }
+
+    #[test]
+    fn every_unprotected_tile_can_be_filled() {
+        let board = Board::new(&settings(3, StartPolicy::SafeNeighborhood));
+        let mines = board
+            .tiles
+            .iter()
+            .filter(|tile| tile.value() == Value::Mine)
+            .count();
+
+        assert_eq!(mines, 72);
+    }
+
+    #[test]
+    fn empty_at_cascades_on_open() {
+        for seed in 0..20 {
+            let gs = settings(seed, StartPolicy::Unprotected);
+            let pos = Position::new(0, 8);
+            let mut board = Board::with_empty_at(&gs, pos).unwrap();
+            board.open_from(pos);
+
+            assert!(board
+                .neighbors_tile_and_pos(pos)
+                .all(|(_, tile)| tile.state() == State::Open));
+        }
+    }
+
+    #[test]
+    fn numbers_count_mines_around() {
+        for seed in 0..10 {
+            let board = Board::new(&GenerationSettings {
+                width: 70,
+                height: 20,
+                mine_count: 300,
+                ..settings(seed, StartPolicy::SafeTile)
+            });
+
+            for (pos, tile) in board.tiles_and_pos() {
+                let mines = board
+                    .neighbors_tile_and_pos(pos)
+                    .filter(|(_, tile)| tile.value() == Value::Mine)
+                    .count();
+                if let Value::Near(n) = tile.value() {
+                    assert_eq!(n as usize, mines);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stacked_mines_are_counted_by_numbers() {
+        for seed in 0..10 {
+            let board = Board::new(&GenerationSettings {
+                mine_count: 150,
+                mines_per_cell: 3,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            });
+
+            assert_eq!(board.mine_count(), 150);
+            assert_eq!(board.mines_per_cell(), 3);
+            assert!(board.tiles.iter().any(|tile| tile.mines() > 1));
+            assert_eq!(
+                board.get_tile(Position::new(4, 4)).unwrap().value(),
+                Value::Near(0)
+            );
+            for (pos, tile) in board.tiles_and_pos() {
+                let mines: usize = board
+                    .neighbors_tile_and_pos(pos)
+                    .map(|(_, tile)| tile.mines() as usize)
+                    .sum();
+                if let Value::Near(n) = tile.value() {
+                    assert_eq!(n as usize, mines);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stacked_mines_fill_every_unprotected_slot() {
+        let board = Board::new(&GenerationSettings {
+            mine_count: 80 * 2,
+            mines_per_cell: 2,
+            ..settings(0, StartPolicy::SafeTile)
+        });
+
+        assert!(board
+            .tiles_and_pos()
+            .all(|(pos, tile)| tile.mines() == if pos == Position::new(4, 4) { 0 } else { 2 }));
+        assert_eq!(
+            board.get_tile(Position::new(4, 4)).unwrap().value(),
+            Value::Near(16)
+        );
+    }
+
+    #[test]
+    fn stacked_mines_leave_a_cell_free_without_protection() {
+        for seed in 0..20 {
+            let board = Board::new(&GenerationSettings {
+                seed: Seed::new(seed),
+                width: 3,
+                height: 3,
+                mine_count: 16,
+                mines_per_cell: 2,
+                start_policy: StartPolicy::Unprotected,
+                ..Default::default()
+            });
+
+            assert_eq!(board.mine_count(), 16);
+            assert!(board.tiles.iter().any(|tile| tile.mines() == 0));
+        }
+    }
+
+    #[test]
+    fn flags_are_counted_up_to_mines_per_cell() {
+        let mut board = Board::new(&GenerationSettings {
+            mine_count: 20,
+            mines_per_cell: 3,
+            ..settings(0, StartPolicy::SafeTile)
+        });
+        let pos = Position::new(0, 0);
+
+        for flags in 1..=3 {
+            assert!(board.flag_from(pos));
+            assert_eq!(board.get_tile(pos).unwrap().flags(), flags);
+        }
+        assert!(board.flag_from(pos));
+        assert_eq!(board.get_tile(pos).unwrap().state(), State::Closed);
+
+        // Boards with one mine per cell toggle their flags.
+        let mut board = Board::new(&settings(0, StartPolicy::SafeTile));
+        board.flag_from(pos);
+        board.flag_from(pos);
+        assert_eq!(board.get_tile(pos).unwrap().state(), State::Closed);
+    }
+
+    #[test]
+    fn chord_counts_every_flag() {
+        // Two mines in the corner, so the number next to them needs two flags.
+        let mut board = Board::from_mines(Grid::new(3, 3, Shape::Bounded), [0, 0]);
+        let (mine, number) = (Position::new(0, 0), Position::new(1, 1));
+        board.open_from(number);
+
+        board.flag_from(mine);
+        assert!(board.chord(number).opened.is_empty());
+        board.flag_from(mine);
+        let chord = board.chord(number);
+        assert_eq!(chord.opened.len(), 7);
+        assert!(chord.mines_hit.is_empty());
+    }
+
+    #[test]
+    fn cascade_matches_neighbor_search() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                width: 130,
+                height: 40,
+                mine_count: 500,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let mut board = Board::new(&gs);
+            board.flag_from(Position::new(0, 0));
+
+            let area = board.flood_area(gs.start_pos, |_, tile| *tile == Tile::EMPTY_CLOSED);
+            let mut expected: Vec<_> = area
+                .iter()
+                .flat_map(|p| p.neighbors(gs.width, gs.height).chain(std::iter::once(*p)))
+                .filter(|&p| board.get_tile(p).unwrap().state() == State::Closed)
+                .collect();
+            expected.sort_unstable_by_key(|p| (p.y, p.x));
+            expected.dedup();
+
+            let mut opened = board.open_from(gs.start_pos);
+            assert_eq!(opened[0], gs.start_pos);
+            opened.sort_unstable_by_key(|p| (p.y, p.x));
+            assert_eq!(opened, expected);
+        }
+    }
+
+    #[test]
+    fn torus_numbers_count_wrapped_mines() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                mine_count: 20,
+                start_pos: Position::new(0, 0),
+                shape: Shape::Torus,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let board = Board::new(&gs);
+            // Wrapped neighbors of the corner are protected too.
+            assert_ne!(
+                board.get_tile(Position::new(8, 8)).unwrap().value(),
+                Value::Mine
+            );
+
+            for (pos, tile) in board.tiles_and_pos() {
+                assert_eq!(board.neighbors(pos).count(), 8);
+                let mines = board
+                    .neighbors_tile_and_pos(pos)
+                    .filter(|(_, tile)| tile.value() == Value::Mine)
+                    .count();
+                if let Value::Near(n) = tile.value() {
+                    assert_eq!(n as usize, mines);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn torus_cascade_wraps_around_edges() {
+        // Column of mines in the middle, with empty columns on both edges.
+        let mines: Vec<_> = (0..5).map(|y| y * 5 + 2).collect();
+        let mut bounded = Board::from_mines(Grid::new(5, 5, Shape::Bounded), mines.iter().copied());
+        let mut torus = Board::from_mines(Grid::new(5, 5, Shape::Torus), mines.iter().copied());
+
+        assert_eq!(bounded.open_from(Position::new(0, 0)).len(), 10);
+        assert_eq!(torus.open_from(Position::new(0, 0)).len(), 20);
+        assert_eq!(
+            torus.get_tile(Position::new(4, 4)).unwrap().state(),
+            State::Open
+        );
+    }
+
+    #[test]
+    fn torus_no_guess_boards_are_solvable() {
+        for seed in 0..5 {
+            let gs = GenerationSettings {
+                width: 12,
+                height: 12,
+                mine_count: 25,
+                start_pos: Position::new(0, 0),
+                mode: GenerationMode::NoGuess { max_attempts: 1000 },
+                shape: Shape::Torus,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let board = Board::try_new(&gs).unwrap();
+
+            assert!(solver::is_solvable(&board, gs.start_pos));
+        }
+    }
+
+    #[test]
+    fn hex_tiles_have_six_neighbors() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                mine_count: 20,
+                shape: Shape::Hex,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let board = Board::new(&gs);
+
+            for (pos, tile) in board.tiles_and_pos() {
+                let neighbors: Vec<_> = board.neighbors(pos).collect();
+                let inner = (1..8).contains(&pos.x) && (1..8).contains(&pos.y);
+                assert!(neighbors.len() <= 6 && (!inner || neighbors.len() == 6));
+                // Adjacency is symmetric.
+                assert!(neighbors
+                    .iter()
+                    .all(|&n| board.neighbors(n).any(|p| p == pos)));
+
+                let mines = board
+                    .neighbors_tile_and_pos(pos)
+                    .filter(|(_, tile)| tile.value() == Value::Mine)
+                    .count();
+                if let Value::Near(n) = tile.value() {
+                    assert_eq!(n as usize, mines);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_cascade_opens_area_and_border() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                width: 20,
+                height: 15,
+                mine_count: 40,
+                shape: Shape::Hex,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let mut board = Board::new(&gs);
+
+            let area = board.flood_area(gs.start_pos, |_, tile| *tile == Tile::EMPTY_CLOSED);
+            let mut expected: Vec<_> = area
+                .iter()
+                .flat_map(|&p| board.neighbors(p).chain(std::iter::once(p)))
+                .collect();
+            expected.sort_unstable_by_key(|p| (p.y, p.x));
+            expected.dedup();
+
+            let mut opened = board.open_from(gs.start_pos);
+            opened.sort_unstable_by_key(|p| (p.y, p.x));
+            assert_eq!(opened, expected);
+        }
+    }
+
+    #[test]
+    fn hex_no_guess_boards_are_solvable() {
+        for seed in 0..5 {
+            let gs = GenerationSettings {
+                width: 12,
+                height: 12,
+                mine_count: 20,
+                mode: GenerationMode::NoGuess { max_attempts: 1000 },
+                shape: Shape::Hex,
+                ..settings(seed, StartPolicy::SafeNeighborhood)
+            };
+            let board = Board::try_new(&gs).unwrap();
+
+            assert!(solver::is_solvable(&board, gs.start_pos));
+        }
+    }
+
+    #[test]
+    fn empty_at_is_deterministic() {
+        let gs = settings(11, StartPolicy::SafeTile);
+        let pos = Position::new(2, 6);
+
+        assert_eq!(
+            Board::with_empty_at(&gs, pos).unwrap().tiles,
+            Board::with_empty_at(&gs, pos).unwrap().tiles
+        );
+    }
+
+    #[test]
+    fn empty_at_out_of_bounds() {
+        let gs = settings(0, StartPolicy::SafeTile);
+
+        assert_eq!(
+            Board::with_empty_at(&gs, Position::new(9, 0)).unwrap_err(),
+            BoardError::StartOutOfBounds(Position::new(9, 0))
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_errors() {
+        let gs = settings(0, StartPolicy::SafeTile);
+
+        assert_eq!(
+            Board::try_new(&GenerationSettings { width: 0, ..gs }).unwrap_err(),
+            BoardError::ZeroDimension {
+                width: 0,
+                height: 9
+            }
+        );
+        assert_eq!(
+            Board::try_new(&GenerationSettings {
+                width: usize::MAX,
+                ..gs
+            })
+            .unwrap_err(),
+            BoardError::SizeOverflow {
+                width: usize::MAX,
+                height: 9
+            }
+        );
+        assert_eq!(
+            Board::try_new(&GenerationSettings {
+                start_pos: Position::new(2, 9),
+                ..gs
+            })
+            .unwrap_err(),
+            BoardError::StartOutOfBounds(Position::new(2, 9))
+        );
+        let masked = |mask| Grid::masked(mask, Shape::Bounded);
+        assert_eq!(
+            Board::try_with_topology(
+                masked(Mask::from_fn(9, 9, |pos| pos.y > 0)),
+                &GenerationSettings {
+                    start_pos: Position::new(2, 0),
+                    ..gs
+                }
+            )
+            .unwrap_err(),
+            BoardError::StartOutOfBounds(Position::new(2, 0))
+        );
+        assert_eq!(
+            Board::try_with_topology(masked(Mask::from_fn(9, 9, |_| false)), &gs).unwrap_err(),
+            BoardError::EmptyMask
+        );
+        assert_eq!(
+            Board::try_new(&GenerationSettings {
+                mine_count: 81,
+                start_policy: StartPolicy::Unprotected,
+                ..gs
+            })
+            .unwrap_err(),
+            BoardError::TooManyMines {
+                mine_count: 81,
+                max: 80
+            }
+        );
+        assert_eq!(
+            Board::try_new(&GenerationSettings {
+                mine_count: 161,
+                mines_per_cell: 2,
+                start_policy: StartPolicy::Unprotected,
+                ..gs
+            })
+            .unwrap_err(),
+            BoardError::TooManyMines {
+                mine_count: 161,
+                max: 160
+            }
+        );
+        for mines_per_cell in [0, 4] {
+            assert_eq!(
+                Board::try_new(&GenerationSettings {
+                    mines_per_cell,
+                    ..gs
+                })
+                .unwrap_err(),
+                BoardError::MinesPerCell(mines_per_cell)
+            );
+        }
+    }
+
+    #[test]
+    fn no_guess_boards_are_solvable() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                width: 16,
+                height: 16,
+                mine_count: 40,
+                start_pos: Position::new(3, 12),
+                start_policy: StartPolicy::SafeNeighborhood,
+                mode: GenerationMode::NoGuess { max_attempts: 1000 },
+                shape: Shape::Bounded,
+                mines_per_cell: 1,
+            };
+            let board = Board::try_new(&gs).unwrap();
+
+            assert!(solver::is_solvable(&board, gs.start_pos));
+            assert_eq!(board.tiles, Board::try_new(&gs).unwrap().tiles);
+        }
+    }
+
+    #[test]
+    fn no_guess_attempts_are_capped() {
+        // Opening the only safe tile reveals nothing about the two mines around it.
+        let gs = GenerationSettings {
+            width: 3,
+            height: 3,
+            mine_count: 2,
+            start_pos: Position::new(1, 1),
+            mode: GenerationMode::NoGuess { max_attempts: 5 },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Board::try_new(&gs).unwrap_err(),
+            BoardError::AttemptsExhausted { attempts: 5 }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_mines_for_protected_area() {
+        Board::new(&GenerationSettings {
+            mine_count: 73,
+            ..settings(0, StartPolicy::SafeNeighborhood)
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let gs = GenerationSettings {
+            mode: GenerationMode::NoGuess { max_attempts: 100 },
+            ..settings(u64::MAX, StartPolicy::SafeNeighborhood)
+        };
+        let json = serde_json::to_string(&gs).unwrap();
+        let restored: GenerationSettings = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(json, serde_json::to_string(&restored).unwrap());
+        assert_eq!(restored.to_state_seed(), gs.to_state_seed());
+        assert_eq!(
+            Board::new(&restored).tiles,
+            Board::new(&gs).tiles,
+            "stored settings should recreate the same board"
+        );
+
+        let mut board = Board::new(&gs);
+        board.open_from(gs.start_pos);
+        board.flag_from(Position::new(0, 0));
+        let json = serde_json::to_string(&board).unwrap();
+        let restored: Board = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.tiles, board.tiles);
+        assert_eq!(restored.topology(), board.topology());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_inconsistent_board() {
+        // Mine in the corner, but its neighbor claims to have no mines around it.
+        let json = r#"{"width":2,"tiles":[31,0,1,1]}"#;
+
+        assert!(serde_json::from_str::<Board>(json).is_err());
+        assert!(serde_json::from_str::<Board>(r#"{"width":3,"tiles":[0,0]}"#).is_err());
+        assert!(serde_json::from_str::<Board>(r#"{"width":1,"tiles":[255]}"#).is_err());
+        assert!(serde_json::from_str::<Board>(r#"{"width":2,"tiles":[31,1,1,1]}"#).is_ok());
+
+        // Two mines in the corner are only allowed with two mines per cell.
+        let json = r#"{"width":2,"tiles":[30,2,2,2]"#;
+        assert!(serde_json::from_str::<Board>(&format!("{}}}", json)).is_err());
+        let board: Board =
+            serde_json::from_str(&format!(r#"{},"mines_per_cell":2}}"#, json)).unwrap();
+        assert_eq!((board.mine_count(), board.mines_per_cell()), (2, 2));
+    }
+}
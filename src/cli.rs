@@ -0,0 +1,4 @@
+pub mod args;
+pub mod commands;
+pub mod play;
+pub mod render;
@@ -0,0 +1,467 @@
+use std::path::{Path, PathBuf};
+
+use std::ops::Range;
+
+use mineraker::{
+    board::{GenerationMode, GenerationSettings, Seed, StartPolicy},
+    formats::pbm,
+    position::Position,
+    simulation::GuessStrategy,
+    topology::{Mask, Shape},
+};
+
+pub const USAGE: &str = "\
+Usage: mineraker <COMMAND> [OPTIONS]
+
+Commands:
+  generate   Print a board in the text format, or save it with --output
+  solve      Run the solver on a board read from FILE or stdin and print each step
+  play       Play an interactive game on a new board or on a board read from FILE
+  stats      Run the solver on many seeds and report its win rate
+  simulate   Play many seeds with the solver and guesses, and report win rate and losses
+  bench      Time generation and solving of many seeds
+  help       Print this help
+
+Board options:
+  --preset <NAME>   Board size and mines of beginner, intermediate or expert
+  --width <N>       Width of the board
+  --height <N>      Height of the board
+  --mines <N>       Number of mines
+  --seed <N>        Seed of the board, random if not given
+  --start <X,Y>     Start position, which is never a mine, first cell if not given
+  --no-guess        Only generate boards which can be solved without guessing
+
+Board options which generate doesn't take, as the text format can't store them:
+  --mines-per-cell <N>
+                    Largest number of mines in one cell, from 1 (default) to 3
+  --torus           Wrap the edges of the board around to the opposite edges
+  --hex             Use hexagonal tiles with six neighbors each
+  --mask <FILE>     Only use the cells of a PBM bitmap or of rows of # and ., which
+                    also set the width and height
+
+Other options:
+  --output <FILE>   Save the board of generate to a file instead of printing it
+  --count <N>       Number of seeds for stats and bench, 100 if not given
+  --seeds <A..B>    Seeds played by simulate, 0..1000 if not given
+  --strategy <NAME> Guesses of simulate: first, random or probability (default)
+  --threads <N>     Threads used by simulate, all cores if not given
+
+Running mineraker without a command starts play.";
+
+/// Attempts used for boards generated with `--no-guess`.
+const NO_GUESS_ATTEMPTS: usize = 1000;
+
+/// [`Preset`] is a standard difficulty level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl Preset {
+    /// Returns width, height and mine count of the preset.
+    pub fn dimensions(self) -> (usize, usize, usize) {
+        match self {
+            Preset::Beginner => (9, 9, 10),
+            Preset::Intermediate => (16, 16, 40),
+            Preset::Expert => (30, 16, 99),
+        }
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(Preset::Beginner),
+            "intermediate" => Ok(Preset::Intermediate),
+            "expert" => Ok(Preset::Expert),
+            _ => Err(format!(
+                "unknown preset {:?}, expected beginner, intermediate or expert",
+                s
+            )),
+        }
+    }
+}
+
+/// [`Command`] is what the binary was asked to do.
+#[derive(Debug, Clone)]
+pub enum Command {
+    Generate {
+        settings: GenerationSettings,
+        output: Option<PathBuf>,
+    },
+    Solve {
+        input: Option<PathBuf>,
+        start: Option<Position>,
+    },
+    Play {
+        settings: GenerationSettings,
+        mask: Option<Mask>,
+        input: Option<PathBuf>,
+    },
+    Stats {
+        settings: GenerationSettings,
+        mask: Option<Mask>,
+        count: u64,
+    },
+    Bench {
+        settings: GenerationSettings,
+        mask: Option<Mask>,
+        count: u64,
+    },
+    Simulate {
+        settings: GenerationSettings,
+        mask: Option<Mask>,
+        seeds: Range<u64>,
+        strategy: GuessStrategy,
+        threads: Option<usize>,
+    },
+    Help,
+}
+
+const BOARD_FLAGS: &[&str] = &[
+    "--preset",
+    "--width",
+    "--height",
+    "--mines",
+    "--seed",
+    "--start",
+    "--no-guess",
+];
+
+/// Board options which the text format of `generate` can't store.
+const EXTRA_BOARD_FLAGS: &[&str] = &["--mines-per-cell", "--torus", "--hex", "--mask"];
+
+/// Options given on the command line, before they are checked against the command.
+#[derive(Debug, Default)]
+struct Flags {
+    preset: Option<Preset>,
+    width: Option<usize>,
+    height: Option<usize>,
+    mines: Option<usize>,
+    mines_per_cell: Option<u8>,
+    seed: Option<u64>,
+    start: Option<Position>,
+    no_guess: bool,
+    torus: bool,
+    hex: bool,
+    mask: Option<PathBuf>,
+    output: Option<PathBuf>,
+    count: Option<u64>,
+    seeds: Option<Range<u64>>,
+    strategy: Option<GuessStrategy>,
+    threads: Option<usize>,
+    input: Option<PathBuf>,
+}
+
+impl Flags {
+    /// Parses flags accepted by a command. `input` tells whether a positional file
+    /// argument is accepted.
+    fn parse(
+        args: impl IntoIterator<Item = String>,
+        accepted: &[&[&str]],
+        input: bool,
+    ) -> Result<Self, String> {
+        let mut flags = Flags::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if !input || flags.input.is_some() {
+                    return Err(format!("unexpected argument {:?}", arg));
+                }
+                flags.input = Some(arg.into());
+                continue;
+            }
+            if !accepted.iter().any(|flags| flags.contains(&arg.as_str())) {
+                return Err(format!("unknown option {:?} for this command", arg));
+            }
+
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--preset" => flags.preset = Some(value()?.parse()?),
+                "--width" => flags.width = Some(number(&arg, &value()?)?),
+                "--height" => flags.height = Some(number(&arg, &value()?)?),
+                "--mines" => flags.mines = Some(number(&arg, &value()?)?),
+                "--mines-per-cell" => flags.mines_per_cell = Some(number(&arg, &value()?)?),
+                "--seed" => flags.seed = Some(number(&arg, &value()?)?),
+                "--start" => flags.start = Some(position(&arg, &value()?)?),
+                "--no-guess" => flags.no_guess = true,
+                "--torus" => flags.torus = true,
+                "--hex" => flags.hex = true,
+                "--mask" => flags.mask = Some(value()?.into()),
+                "--output" => flags.output = Some(value()?.into()),
+                "--count" => flags.count = Some(number(&arg, &value()?)?),
+                "--seeds" => flags.seeds = Some(range(&arg, &value()?)?),
+                "--strategy" => flags.strategy = Some(value()?.parse()?),
+                "--threads" => flags.threads = Some(number(&arg, &value()?)?),
+                _ => unreachable!("accepted options are handled above"),
+            }
+        }
+        Ok(flags)
+    }
+
+    /// Builds validated settings and the mask of the board. Width, height and mines
+    /// override the preset, which is expert by default. Size of the mask replaces the size
+    /// of the preset, and its first cell is the default start position.
+    fn settings(&self) -> Result<(GenerationSettings, Option<Mask>), String> {
+        let (mut width, mut height, mines) = self.preset.unwrap_or(Preset::Expert).dimensions();
+        if self.mask.is_some() && (self.width.is_some() || self.height.is_some()) {
+            return Err("--width and --height can't be combined with --mask".to_string());
+        }
+        let mask = self.mask.as_deref().map(read_mask).transpose()?;
+        if let Some(mask) = &mask {
+            (width, height) = (mask.width(), mask.height());
+        }
+        let settings = GenerationSettings {
+            seed: Seed::new(self.seed.unwrap_or_else(rand::random)),
+            width: self.width.unwrap_or(width),
+            height: self.height.unwrap_or(height),
+            mine_count: self.mines.unwrap_or(mines),
+            start_pos: self
+                .start
+                .or_else(|| {
+                    mask.as_ref()
+                        .filter(|m| m.cell_count() > 0)
+                        .map(|m| m.position(0))
+                })
+                .unwrap_or_default(),
+            start_policy: if self.no_guess {
+                StartPolicy::SafeNeighborhood
+            } else {
+                StartPolicy::SafeTile
+            },
+            mode: if self.no_guess {
+                GenerationMode::NoGuess {
+                    max_attempts: NO_GUESS_ATTEMPTS,
+                }
+            } else {
+                GenerationMode::Random
+            },
+            shape: match (self.torus, self.hex) {
+                (true, true) => return Err("--torus and --hex can't be combined".to_string()),
+                (true, false) => Shape::Torus,
+                (false, true) => Shape::Hex,
+                (false, false) => Shape::Bounded,
+            },
+            mines_per_cell: self.mines_per_cell.unwrap_or(1),
+        };
+        settings.validate().map_err(|err| err.to_string())?;
+        if let Some(mask) = &mask {
+            settings
+                .validate_in(&settings.grid_with_mask(Some(mask.clone())))
+                .map_err(|err| err.to_string())?;
+        }
+        Ok((settings, mask))
+    }
+}
+
+/// Parses command line arguments, without the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let args: Vec<_> = args.into_iter().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(Command::Help);
+    }
+
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek() {
+        Some(arg) if !arg.starts_with("--") => args.next().unwrap(),
+        _ => "play".to_string(),
+    };
+
+    match command.as_str() {
+        "generate" => {
+            let flags = Flags::parse(args, &[BOARD_FLAGS, &["--output"]], false)?;
+            Ok(Command::Generate {
+                settings: flags.settings()?.0,
+                output: flags.output,
+            })
+        }
+        "solve" => {
+            let flags = Flags::parse(args, &[&["--start"]], true)?;
+            Ok(Command::Solve {
+                input: flags.input,
+                start: flags.start,
+            })
+        }
+        "play" => {
+            let flags = Flags::parse(args, &[BOARD_FLAGS, EXTRA_BOARD_FLAGS], true)?;
+            let (settings, mask) = flags.settings()?;
+            Ok(Command::Play {
+                settings,
+                mask,
+                input: flags.input,
+            })
+        }
+        "stats" | "bench" => {
+            let flags = Flags::parse(args, &[BOARD_FLAGS, EXTRA_BOARD_FLAGS, &["--count"]], false)?;
+            let ((settings, mask), count) = (flags.settings()?, flags.count.unwrap_or(100));
+            Ok(if command == "stats" {
+                Command::Stats {
+                    settings,
+                    mask,
+                    count,
+                }
+            } else {
+                Command::Bench {
+                    settings,
+                    mask,
+                    count,
+                }
+            })
+        }
+        "simulate" => {
+            let flags = Flags::parse(
+                args,
+                &[
+                    BOARD_FLAGS,
+                    EXTRA_BOARD_FLAGS,
+                    &["--seeds", "--strategy", "--threads"],
+                ],
+                false,
+            )?;
+            let (settings, mask) = flags.settings()?;
+            Ok(Command::Simulate {
+                settings,
+                mask,
+                seeds: flags.seeds.unwrap_or(0..1000),
+                strategy: flags.strategy.unwrap_or_default(),
+                threads: flags.threads,
+            })
+        }
+        "help" => Ok(Command::Help),
+        _ => Err(format!("unknown command {:?}", command)),
+    }
+}
+
+/// Reads a mask from a PBM bitmap, or from rows of `#` and `.` if the file is not a bitmap.
+fn read_mask(path: &Path) -> Result<Mask, String> {
+    let invalid = |err: &dyn std::fmt::Display| format!("invalid mask {}: {}", path.display(), err);
+    let bytes =
+        std::fs::read(path).map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+    if bytes.starts_with(b"P1") || bytes.starts_with(b"P4") {
+        return pbm::import(&bytes).map_err(|err| invalid(&err));
+    }
+    String::from_utf8_lossy(&bytes)
+        .parse()
+        .map_err(|err| invalid(&err))
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {:?} for {}", value, name))
+}
+
+fn position(name: &str, value: &str) -> Result<Position, String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected X,Y for {}, found {:?}", name, value))?;
+    Ok(Position::new(number(name, x)?, number(name, y)?))
+}
+
+fn range(name: &str, value: &str) -> Result<Range<u64>, String> {
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END for {}, found {:?}", name, value))?;
+    Ok(number(name, start)?..number(name, end)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use mineraker::{
+        board::GenerationMode, position::Position, simulation::GuessStrategy, topology::Shape,
+    };
+
+    use super::{parse, Command};
+
+    fn command(args: &str) -> Result<Command, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    fn settings(args: &str) -> Result<(usize, usize, usize, u64), String> {
+        match command(args)? {
+            Command::Play { settings: gs, .. } | Command::Generate { settings: gs, .. } => {
+                Ok((gs.width, gs.height, gs.mine_count, gs.seed.get()))
+            }
+            _ => Err("no settings".to_string()),
+        }
+    }
+
+    #[test]
+    fn presets_and_overrides() {
+        assert_eq!(settings("--preset beginner --seed 3"), Ok((9, 9, 10, 3)));
+        assert_eq!(
+            settings("play --seed 1 --width 20 --mines 50"),
+            Ok((20, 16, 50, 1))
+        );
+        assert_eq!(
+            settings("generate --preset intermediate --seed 2"),
+            Ok((16, 16, 40, 2))
+        );
+    }
+
+    #[test]
+    fn subcommands() {
+        assert!(matches!(command("help"), Ok(Command::Help)));
+        assert!(matches!(
+            command("solve board.txt --start 3,4"),
+            Ok(Command::Solve { input: Some(_), start: Some(pos) }) if pos == Position::new(3, 4)
+        ));
+        assert!(matches!(
+            command("stats --count 7 --no-guess"),
+            Ok(Command::Stats { count: 7, settings, .. }) if matches!(settings.mode, GenerationMode::NoGuess { .. })
+        ));
+        assert!(matches!(
+            command("play --torus"),
+            Ok(Command::Play { settings, .. }) if settings.shape == Shape::Torus
+        ));
+        assert!(matches!(
+            command("stats --hex"),
+            Ok(Command::Stats { settings, .. }) if settings.shape == Shape::Hex
+        ));
+        assert!(matches!(
+            command("bench"),
+            Ok(Command::Bench { count: 100, .. })
+        ));
+        assert!(matches!(
+            command("simulate --seeds 10..20 --strategy first --threads 2"),
+            Ok(Command::Simulate { seeds, strategy: GuessStrategy::First, threads: Some(2), .. })
+                if seeds == (10..20)
+        ));
+        assert!(matches!(
+            command("simulate"),
+            Ok(Command::Simulate { seeds, strategy: GuessStrategy::LowestProbability, threads: None, .. })
+                if seeds == (0..1000)
+        ));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(settings("--preset hard").is_err());
+        assert!(settings("--width").is_err());
+        assert!(settings("--mines x").is_err());
+        assert!(settings("--width 3 --height 3 --mines 9").is_err());
+        assert!(settings("--frobnicate").is_err());
+        assert!(settings("--torus --hex").is_err());
+        assert!(settings("--mask missing-mask.txt").is_err());
+        assert!(settings("--mask mask.txt --width 9").is_err());
+        assert!(command("solve --count 3").is_err());
+        assert!(command("solve a.txt b.txt").is_err());
+        assert!(command("generate board.txt").is_err());
+        assert!(command("generate --torus").is_err());
+        assert!(command("generate --hex").is_err());
+        assert!(command("generate --mask mask.txt").is_err());
+        assert!(command("generate --mines-per-cell 2").is_err());
+        assert!(command("frobnicate").is_err());
+        assert!(command("solve --start 3").is_err());
+        assert!(command("simulate --seeds 10").is_err());
+        assert!(command("simulate --strategy best").is_err());
+        assert!(command("stats --seeds 0..10").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    fs,
+    io::{self, Read},
+    ops::Range,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use mineraker::{
+    board::{Board, GenerationSettings, Seed},
+    game::{Game, Move},
+    position::Position,
+    simulation::{GuessStrategy, Simulation, PROGRESS_BUCKETS, Z_95},
+    solver::{Solver, Step},
+    topology::{Grid, Mask},
+};
+
+/// Reads a board in the text format from a file, or from stdin if no file or `-` is given.
+pub fn read_board(input: Option<&Path>) -> Result<Board, String> {
+    let text = match input {
+        Some(path) if path != Path::new("-") => fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?,
+        _ => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("can't read stdin: {}", err))?;
+            text
+        }
+    };
+    text.parse()
+        .map_err(|err| format!("invalid board: {}", err))
+}
+
+/// Writes a board as its solution followed by the player view, which keeps both mines and
+/// tile states.
+fn board_text(board: &Board) -> String {
+    format!("{}\n{}", board.solution(), board)
+}
+
+fn describe_move(mv: Move) -> String {
+    let (name, pos) = match mv {
+        Move::Open(pos) => ("open", pos),
+        Move::Flag(pos) => ("flag", pos),
+        Move::Chord(pos) => ("chord", pos),
+    };
+    format!("{} ({}, {})", name, pos.x, pos.y)
+}
+
+/// Creates a game on the grid from the settings with the start position opened.
+fn started_game(grid: &Grid, settings: &GenerationSettings) -> Result<Game, String> {
+    let board = Board::try_with_topology(grid.clone(), settings).map_err(|err| err.to_string())?;
+    let mut game = Game::new(board);
+    game.play(Move::Open(settings.start_pos))
+        .map_err(|err| err.to_string())?;
+    Ok(game)
+}
+
+/// Generates a board with the start position opened and prints or saves it. The text
+/// format has no shape, mask or mine counts, so only bounded grids without a mask and with
+/// one mine per cell can be generated. Other boards are rejected with the arguments.
+pub fn generate(settings: &GenerationSettings, output: Option<&Path>) -> Result<(), String> {
+    let text = board_text(started_game(&settings.grid(), settings)?.board());
+    match output {
+        Some(path) => {
+            fs::write(path, text).map_err(|err| format!("can't write {}: {}", path.display(), err))
+        }
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// Runs the solver on a board and prints the moves and the board after each step.
+pub fn solve(input: Option<&Path>, start: Option<Position>) -> Result<(), String> {
+    let mut game = Game::new(read_board(input)?);
+    if let Some(start) = start {
+        game.play(Move::Open(start))
+            .map_err(|err| err.to_string())?;
+    }
+    if game.status().is_over() {
+        return Err("game is already over".to_string());
+    }
+
+    let mut solver = Solver::new(game);
+    for step in 1.. {
+        match solver.step() {
+            Step::Progress(moves) => {
+                let moves: Vec<_> = moves.into_iter().map(describe_move).collect();
+                println!("Step {}: {}", step, moves.join(", "));
+                println!("{}", solver.game().board());
+            }
+            Step::Solved => {
+                println!("Solved in {} steps.", step - 1);
+                break;
+            }
+            Step::Stuck => {
+                println!("Stuck after {} steps:\n", step - 1);
+                print!("{}", board_text(solver.game().board()));
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the solver on boards of consecutive seeds and prints its win rate.
+pub fn stats(settings: &GenerationSettings, mask: Option<Mask>, count: u64) -> Result<(), String> {
+    let grid = settings.grid_with_mask(mask);
+    let mut solved = 0;
+    for seed in 0..count {
+        let settings = GenerationSettings {
+            seed: Seed::new(settings.seed.get().wrapping_add(seed)),
+            ..*settings
+        };
+        if Solver::new(started_game(&grid, &settings)?).solve() == Step::Solved {
+            solved += 1;
+        }
+    }
+
+    println!(
+        "Boards: {} ({}x{}, {} mines, seeds from {})",
+        count,
+        settings.width,
+        settings.height,
+        settings.mine_count,
+        settings.seed.get()
+    );
+    println!("Solved: {}", solved);
+    println!("Stuck:  {}", count - solved);
+    if count > 0 {
+        println!("Win rate: {:.1}%", solved as f64 * 100.0 / count as f64);
+    }
+    Ok(())
+}
+
+/// Times generation and solving of boards of consecutive seeds.
+pub fn bench(settings: &GenerationSettings, mask: Option<Mask>, count: u64) -> Result<(), String> {
+    let grid = settings.grid_with_mask(mask);
+    let (mut generation, mut solving) = (Duration::ZERO, Duration::ZERO);
+    for seed in 0..count {
+        let settings = GenerationSettings {
+            seed: Seed::new(settings.seed.get().wrapping_add(seed)),
+            ..*settings
+        };
+
+        let start = Instant::now();
+        let game = started_game(&grid, &settings)?;
+        generation += start.elapsed();
+
+        let start = Instant::now();
+        Solver::new(game).solve();
+        solving += start.elapsed();
+    }
+
+    let per_board = |total: Duration| total.as_secs_f64() * 1000.0 / count.max(1) as f64;
+    println!("Boards:     {}", count);
+    println!(
+        "Generation: {:.3} ms per board, {:.3} s total",
+        per_board(generation),
+        generation.as_secs_f64()
+    );
+    println!(
+        "Solving:    {:.3} ms per board, {:.3} s total",
+        per_board(solving),
+        solving.as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Plays boards of a range of seeds with the solver and a guessing strategy, and prints the
+/// win rate with its confidence interval and where the lost games ended.
+pub fn simulate(
+    settings: GenerationSettings,
+    mask: Option<Mask>,
+    seeds: Range<u64>,
+    strategy: GuessStrategy,
+    threads: Option<usize>,
+) -> Result<(), String> {
+    let simulation = Simulation {
+        settings,
+        mask,
+        seeds,
+        strategy,
+    };
+    let start = Instant::now();
+    let report = match threads {
+        Some(threads) => simulation.run_on(threads),
+        None => simulation.run(),
+    }
+    .map_err(|err| err.to_string())?;
+
+    println!(
+        "Boards: {} ({}x{}, {} mines, seeds {}..{}, {:?} guesses)",
+        report.games,
+        settings.width,
+        settings.height,
+        settings.mine_count,
+        simulation.seeds.start,
+        simulation.seeds.end,
+        strategy
+    );
+    let (low, high) = report.confidence_interval(Z_95);
+    println!(
+        "Win rate: {:.2}% (95% CI {:.2}% to {:.2}%), {} won, {} lost",
+        report.win_rate() * 100.0,
+        low * 100.0,
+        high * 100.0,
+        report.wins,
+        report.losses()
+    );
+    println!("Guesses:  {:.2} per game", report.average_guesses());
+    println!("Time:     {:.3} s", start.elapsed().as_secs_f64());
+
+    if report.losses() > 0 {
+        println!("\nLosses by tiles opened:");
+        for (bucket, &losses) in report.losses_by_progress.iter().enumerate() {
+            println!(
+                "  {:>3}% to {:>3}%  {}",
+                bucket * 100 / PROGRESS_BUCKETS,
+                (bucket + 1) * 100 / PROGRESS_BUCKETS,
+                losses
+            );
+        }
+        println!("Losses by guess:");
+        for (guess, &losses) in report.losses_by_guess.iter().enumerate() {
+            println!("  {:>8}  {}", guess + 1, losses);
+        }
+    }
+    Ok(())
+}
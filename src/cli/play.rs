@@ -0,0 +1,190 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::Instant,
+};
+
+use mineraker::{
+    board::{Board, GenerationSettings},
+    game::{Game, Move, Status},
+    position::Position,
+    topology::{Mask, Topology},
+};
+
+use crate::cli::render::Colored;
+
+const HELP: &str = "\
+Commands:
+  o X Y   open the tile at column X and row Y, also just X Y
+  f X Y   flag or unflag the tile
+  c X Y   chord around an open number
+  q       quit";
+
+/// [`Input`] is a single line of player input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Move(Move),
+    Help,
+    Quit,
+}
+
+fn parse_input(line: &str) -> Result<Input, String> {
+    let words: Vec<_> = line.split_whitespace().collect();
+    let pos = |x: &str, y: &str| match (x.parse(), y.parse()) {
+        (Ok(x), Ok(y)) => Ok(Position::new(x, y)),
+        _ => Err(format!("invalid coordinates {} {}", x, y)),
+    };
+    match words[..] {
+        ["q" | "quit"] => Ok(Input::Quit),
+        ["h" | "help" | "?"] => Ok(Input::Help),
+        ["o" | "open", x, y] | [x, y] => Ok(Input::Move(Move::Open(pos(x, y)?))),
+        ["f" | "flag", x, y] => Ok(Input::Move(Move::Flag(pos(x, y)?))),
+        ["c" | "chord", x, y] => Ok(Input::Move(Move::Chord(pos(x, y)?))),
+        _ => Err(format!(
+            "unknown command {:?}, type h for help",
+            line.trim()
+        )),
+    }
+}
+
+/// Runs an interactive game in the terminal. Without a given board, the board is generated
+/// on the first open with the opened tile as the start position, so the first tile is
+/// never a mine.
+pub fn run(
+    settings: &GenerationSettings,
+    mask: Option<Mask>,
+    board: Option<Board>,
+) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut lines = stdin.lock().lines();
+
+    // Placeholder shown before the board is generated.
+    let grid = board.as_ref().map_or_else(
+        || settings.grid_with_mask(mask),
+        |board| board.topology().clone(),
+    );
+    let blank = Board::try_with_topology(
+        grid.clone(),
+        &GenerationSettings {
+            mine_count: 0,
+            start_pos: grid.to_position(0),
+            ..*settings
+        },
+    )
+    .expect("board without mines is valid");
+    let mut game = board.map(Game::new);
+    let mut started = None;
+    let mut message = String::from("Type h for help.");
+
+    loop {
+        let board = game.as_ref().map_or(&blank, Game::board);
+        let mines_left = game
+            .as_ref()
+            .map_or(settings.mine_count as isize, Game::mines_left);
+        let elapsed = started.map_or(0, |start: Instant| start.elapsed().as_secs());
+        write!(stdout, "\x1b[2J\x1b[H")?;
+        writeln!(stdout, "Mines: {:<6} Time: {}s\n", mines_left, elapsed)?;
+        write!(
+            stdout,
+            "{}",
+            Colored {
+                board,
+                reveal: false
+            }
+        )?;
+        write!(stdout, "\n{}\n> ", message)?;
+        stdout.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        let mv = match parse_input(&line) {
+            Ok(Input::Move(mv)) => mv,
+            Ok(Input::Help) => {
+                message = HELP.to_string();
+                continue;
+            }
+            Ok(Input::Quit) => return Ok(()),
+            Err(err) => {
+                message = err;
+                continue;
+            }
+        };
+
+        let game = match game {
+            Some(ref mut game) => game,
+            None => match mv {
+                Move::Open(pos) if blank.contains(pos) => {
+                    let settings = GenerationSettings {
+                        start_pos: pos,
+                        ..*settings
+                    };
+                    match Board::try_with_topology(grid.clone(), &settings) {
+                        Ok(new) => game.insert(Game::new(new)),
+                        Err(err) => {
+                            message = err.to_string();
+                            continue;
+                        }
+                    }
+                }
+                Move::Open(pos) => {
+                    message = format!("position ({}, {}) is outside the board", pos.x, pos.y);
+                    continue;
+                }
+                _ => {
+                    message = "Open a tile first.".to_string();
+                    continue;
+                }
+            },
+        };
+
+        started.get_or_insert_with(Instant::now);
+        message = match game.play(mv) {
+            Ok(_) => String::new(),
+            Err(err) => err.to_string(),
+        };
+
+        if game.status().is_over() {
+            let elapsed = started.map_or(0.0, |start| start.elapsed().as_secs_f64());
+            write!(stdout, "\x1b[2J\x1b[H")?;
+            match game.status() {
+                Status::Won => writeln!(stdout, "You won in {:.1}s!\n", elapsed)?,
+                Status::Lost { exploded_at } => writeln!(
+                    stdout,
+                    "Boom! You hit a mine at ({}, {}) after {:.1}s.\n",
+                    exploded_at.x, exploded_at.y, elapsed
+                )?,
+                _ => {}
+            }
+            write!(
+                stdout,
+                "{}",
+                Colored {
+                    board: game.board(),
+                    reveal: true
+                }
+            )?;
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mineraker::{game::Move, position::Position};
+
+    use super::{parse_input, Input};
+
+    #[test]
+    fn parses_commands() {
+        let pos = Position::new(3, 12);
+        assert_eq!(parse_input("o 3 12"), Ok(Input::Move(Move::Open(pos))));
+        assert_eq!(parse_input(" 3  12 "), Ok(Input::Move(Move::Open(pos))));
+        assert_eq!(parse_input("flag 3 12"), Ok(Input::Move(Move::Flag(pos))));
+        assert_eq!(parse_input("c 3 12"), Ok(Input::Move(Move::Chord(pos))));
+        assert_eq!(parse_input("q"), Ok(Input::Quit));
+        assert!(parse_input("o 3").is_err());
+        assert!(parse_input("o -1 2").is_err());
+    }
+}
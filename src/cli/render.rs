@@ -0,0 +1,125 @@
+use std::fmt;
+
+use mineraker::{board::Board, topology::Shape};
+
+const RESET: &str = "\x1b[0m";
+/// Color of the tiles copied from the opposite edges of a torus.
+const WRAPPED: &str = "\x1b[2;90m";
+
+/// [`Colored`] displays a board with ANSI colors and coordinate labels. Tiles use the
+/// characters of [`Display`] for [`Board`]. If `reveal` is set, closed mines are shown
+/// and wrong flags are marked with `x`. Boards with [`Shape::Torus`] are framed with
+/// dimmed copies of the opposite edges, which are the neighbors of the edge tiles. Odd
+/// rows of [`Shape::Hex`] boards are shifted right by half a tile.
+///
+/// [`Display`]: std::fmt::Display
+pub struct Colored<'a> {
+    pub board: &'a Board,
+    pub reveal: bool,
+}
+
+/// Returns the ANSI color code of a tile character.
+fn color(c: char) -> &'static str {
+    match c {
+        '1' => "\x1b[1;34m",
+        '2' => "\x1b[32m",
+        '3' => "\x1b[1;31m",
+        '4' => "\x1b[35m",
+        '5' => "\x1b[33m",
+        '6' => "\x1b[36m",
+        '7' => "\x1b[1;37m",
+        '8' => "\x1b[90m",
+        '*' => "\x1b[1;37;41m",
+        '?' => "\x1b[1;33m",
+        'x' => "\x1b[1;31m",
+        '#' => "\x1b[2m",
+        _ => "",
+    }
+}
+
+impl Colored<'_> {
+    /// Returns the characters of the tiles, row by row.
+    fn rows(&self) -> Vec<Vec<char>> {
+        let view = self.board.to_string();
+        let solution = self.board.solution().to_string();
+        view.lines()
+            .zip(solution.lines())
+            .map(|(view, solution)| {
+                view.chars()
+                    .zip(solution.chars())
+                    .map(|(c, s)| match (c, s) {
+                        ('#', '*') if self.reveal => '*',
+                        ('?', s) if self.reveal && s != '*' => 'x',
+                        _ => c,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Writes a copy of an opposite edge tile, which is dimmed regardless of its color.
+fn write_wrapped(f: &mut fmt::Formatter<'_>, c: char) -> fmt::Result {
+    write!(f, " {}{}{}", WRAPPED, c, RESET)
+}
+
+impl fmt::Display for Colored<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.board.width();
+        let label_width = (self.board.height() - 1).to_string().len();
+        let torus = self.board.topology().shape == Shape::Torus;
+        let hex = self.board.topology().shape == Shape::Hex;
+        // Tiles are shifted right by the copied edge column of a torus.
+        let margin = if torus { "  " } else { "" };
+
+        // Column labels are written vertically, one line per digit.
+        let digits = (width - 1).to_string().len();
+        for d in (0..digits).rev() {
+            write!(f, "{:label_width$} {}", "", margin)?;
+            for x in 0..width {
+                let place = 10usize.pow(d as u32);
+                match x / place {
+                    0 if d > 0 => write!(f, "  ")?,
+                    n => write!(f, " {}", n % 10)?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        let rows = self.rows();
+        let write_row =
+            |f: &mut fmt::Formatter<'_>, label: String, row: &[char], dim: bool, indent: bool| {
+                write!(
+                    f,
+                    "{:>label_width$} {}",
+                    label,
+                    if indent { " " } else { "" }
+                )?;
+                if torus {
+                    write_wrapped(f, row[width - 1])?;
+                }
+                for &c in row {
+                    if dim {
+                        write_wrapped(f, c)?;
+                    } else {
+                        write!(f, " {}{}{}", color(c), c, RESET)?;
+                    }
+                }
+                if torus {
+                    write_wrapped(f, row[0])?;
+                }
+                writeln!(f)
+            };
+
+        if torus {
+            write_row(f, String::new(), &rows[rows.len() - 1], true, false)?;
+        }
+        for (y, row) in rows.iter().enumerate() {
+            write_row(f, y.to_string(), row, false, hex && y % 2 == 1)?;
+        }
+        if torus {
+            write_row(f, String::new(), &rows[0], true, false)?;
+        }
+        Ok(())
+    }
+}
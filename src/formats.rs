@@ -0,0 +1,2 @@
+pub mod pbm;
+pub mod rawvf;
@@ -0,0 +1,170 @@
+use crate::{position::Position, topology::Mask};
+
+/// [`PbmError`] describes why a PBM bitmap could not be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PbmError {
+    /// Input doesn't start with `P1` or `P4`.
+    BadMagic,
+    /// Width or height is missing or not a number.
+    InvalidHeader(&'static str),
+    /// Plain bitmap contains a character other than `0`, `1` and whitespace.
+    InvalidPixel(char),
+    /// Input ended before all pixels were read.
+    UnexpectedEnd,
+}
+
+impl std::fmt::Display for PbmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PbmError::BadMagic => write!(f, "not a PBM bitmap"),
+            PbmError::InvalidHeader(field) => write!(f, "invalid {} in the header", field),
+            PbmError::InvalidPixel(c) => write!(f, "invalid pixel character {:?}", c),
+            PbmError::UnexpectedEnd => write!(f, "bitmap ended before all pixels"),
+        }
+    }
+}
+
+impl std::error::Error for PbmError {}
+
+/// Reads the bytes of a PBM file, skipping comments between header fields.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    /// Skips whitespace and comments, which run from `#` to the end of the line.
+    fn skip_space(&mut self) {
+        loop {
+            match self.bytes.first() {
+                Some(b) if b.is_ascii_whitespace() => self.bytes = &self.bytes[1..],
+                Some(b'#') => {
+                    let end = self.bytes.iter().position(|&b| b == b'\n');
+                    self.bytes = &self.bytes[end.unwrap_or(self.bytes.len())..];
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn number(&mut self, field: &'static str) -> Result<usize, PbmError> {
+        self.skip_space();
+        let len = self
+            .bytes
+            .iter()
+            .position(|b| !b.is_ascii_digit())
+            .unwrap_or(self.bytes.len());
+        let (digits, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(PbmError::InvalidHeader(field))
+    }
+}
+
+/// Imports a [`Mask`] from a PBM bitmap in the plain `P1` or the binary `P4` format. Black
+/// pixels, which are ones, are the cells of the mask, so the shape of a board can be
+/// drawn in black on white.
+///
+/// # Examples
+/// ```
+/// use mineraker::formats::pbm;
+/// use mineraker::position::Position;
+///
+/// let mask = pbm::import(b"P1\n# heart\n5 4\n01010\n11111\n01110\n00100\n").unwrap();
+/// assert_eq!((mask.width(), mask.height()), (5, 4));
+/// assert_eq!(mask.cell_count(), 11);
+/// assert!(!mask.contains(Position::new(0, 0)));
+/// assert_eq!(pbm::import(&pbm::export(&mask)), Ok(mask));
+/// ```
+pub fn import(bytes: &[u8]) -> Result<Mask, PbmError> {
+    let binary = match bytes.get(..2) {
+        Some(b"P1") => false,
+        Some(b"P4") => true,
+        _ => return Err(PbmError::BadMagic),
+    };
+    let mut reader = Reader { bytes: &bytes[2..] };
+    let width = reader.number("width")?;
+    let height = reader.number("height")?;
+    let len = width
+        .checked_mul(height)
+        .ok_or(PbmError::InvalidHeader("size"))?;
+
+    let pixels: Vec<bool> = if binary {
+        // Exactly one whitespace character separates the header from the pixels.
+        let stride = width.div_ceil(8);
+        let raster = reader
+            .bytes
+            .get(1..)
+            .filter(|raster| raster.len() >= stride * height)
+            .ok_or(PbmError::UnexpectedEnd)?;
+        (0..len)
+            .map(|i| {
+                let pos = Position::from_index(i, width);
+                raster[pos.y * stride + pos.x / 8] & (0x80 >> (pos.x % 8)) != 0
+            })
+            .collect()
+    } else {
+        let mut pixels = Vec::with_capacity(len.min(reader.bytes.len()));
+        while pixels.len() < len {
+            reader.skip_space();
+            match reader.bytes.split_first() {
+                Some((b'0', _)) => pixels.push(false),
+                Some((b'1', _)) => pixels.push(true),
+                Some((&b, _)) => return Err(PbmError::InvalidPixel(b as char)),
+                None => return Err(PbmError::UnexpectedEnd),
+            }
+            reader.bytes = &reader.bytes[1..];
+        }
+        pixels
+    };
+
+    Ok(Mask::from_fn(width, height, |pos| {
+        pixels[pos.to_index(width)]
+    }))
+}
+
+/// Exports a [`Mask`] as a binary `P4` bitmap with the cells as black pixels.
+pub fn export(mask: &Mask) -> Vec<u8> {
+    let (width, height) = (mask.width(), mask.height());
+    let mut out = format!("P4\n{} {}\n", width, height).into_bytes();
+    let stride = width.div_ceil(8);
+    let raster = out.len();
+    out.resize(raster + stride * height, 0);
+    for i in 0..mask.cell_count() {
+        let pos = mask.position(i);
+        out[raster + pos.y * stride + pos.x / 8] |= 0x80 >> (pos.x % 8);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formats::pbm::{export, import, PbmError},
+        position::Position,
+    };
+
+    #[test]
+    fn binary_rows_are_padded_to_bytes() {
+        // Rows of 10 pixels take two bytes, the last six bits are padding.
+        let raster = [0b1000_0000, 0b0100_0000, 0xff, 0b1100_0000];
+        let mask = import(&[b"P4 10 2\n".as_slice(), &raster].concat()).unwrap();
+
+        assert_eq!(mask.cell_count(), 12);
+        assert!(mask.contains(Position::new(0, 0)));
+        assert!(mask.contains(Position::new(9, 0)));
+        assert!(!mask.contains(Position::new(8, 0)));
+        assert_eq!(mask.to_string(), "#........#\n##########\n");
+        assert_eq!(export(&mask), [b"P4\n10 2\n".as_slice(), &raster].concat());
+    }
+
+    #[test]
+    fn rejects_invalid_bitmaps() {
+        assert_eq!(import(b"P2 1 1\n1"), Err(PbmError::BadMagic));
+        assert_eq!(import(b"P1 x 1\n1"), Err(PbmError::InvalidHeader("width")));
+        assert_eq!(import(b"P1 2 2\n1 0 2 1"), Err(PbmError::InvalidPixel('2')));
+        assert_eq!(import(b"P1 2 2\n1 0 1"), Err(PbmError::UnexpectedEnd));
+        assert_eq!(import(b"P4 9 1\n\xff"), Err(PbmError::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,703 @@
+use std::{fmt::Write, time::Duration};
+
+use crate::{
+    board::Board,
+    game::{Game, Move},
+    position::Position,
+    replay::Event,
+    tile::Value,
+    topology::{Grid, Shape, Topology},
+};
+
+/// Size of a square in pixels, used for the pixel coordinates of exported mouse events.
+const SQUARE_SIZE: usize = 16;
+
+/// [`RawvfErrorKind`] describes what went wrong when importing a RAWVF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawvfErrorKind {
+    /// File has no `Board:` section.
+    MissingBoard,
+    /// Header field has a value which is not a number.
+    InvalidHeader(&'static str),
+    /// Board section contains a character other than `*` and `0`.
+    InvalidCharacter(char),
+    /// Board line has a different number of squares than the width.
+    LineLength { expected: usize, found: usize },
+    /// Board section has a different number of lines than the height.
+    LineCount { expected: usize, found: usize },
+    /// Board section has a different number of mines than the `Mines` header.
+    MineCount { expected: usize, found: usize },
+    /// Mouse event can't be parsed.
+    InvalidEvent,
+}
+
+impl std::fmt::Display for RawvfErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawvfErrorKind::MissingBoard => write!(f, "file has no board section"),
+            RawvfErrorKind::InvalidHeader(field) => {
+                write!(f, "header field {} is not a number", field)
+            }
+            RawvfErrorKind::InvalidCharacter(c) => write!(f, "invalid square character {:?}", c),
+            RawvfErrorKind::LineLength { expected, found } => write!(
+                f,
+                "expected {} squares on the line, found {}",
+                expected, found
+            ),
+            RawvfErrorKind::LineCount { expected, found } => write!(
+                f,
+                "expected {} lines in the board, found {}",
+                expected, found
+            ),
+            RawvfErrorKind::MineCount { expected, found } => write!(
+                f,
+                "expected {} mines on the board, found {}",
+                expected, found
+            ),
+            RawvfErrorKind::InvalidEvent => write!(f, "invalid mouse event"),
+        }
+    }
+}
+
+/// [`RawvfError`] is returned when a RAWVF file can't be imported. Line is 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawvfError {
+    pub line: usize,
+    pub kind: RawvfErrorKind,
+}
+
+impl std::fmt::Display for RawvfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for RawvfError {}
+
+/// [`ExportError`] is returned when a board can't be stored in a RAWVF file, which only has
+/// rectangles of squares holding at most one mine each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportError {
+    /// Board is not a bounded rectangle, such as a board with a mask or a torus or hex
+    /// shape.
+    Shape,
+    /// Cells of the board can hold several mines.
+    MinesPerCell(u8),
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Shape => write!(f, "RAWVF files can only store bounded rectangles"),
+            ExportError::MinesPerCell(count) => {
+                write!(f, "RAWVF files can't store cells with {} mines", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// [`Rawvf`] is a game imported from a RAWVF file.
+#[derive(Debug, Clone)]
+pub struct Rawvf {
+    /// Header fields before the board section, such as `Player` or `Timestamp`.
+    pub header: Vec<(String, String)>,
+    /// Board with all tiles closed.
+    pub board: Board,
+    /// Moves made by the mouse events, in the order they were made.
+    pub events: Vec<Event>,
+}
+
+impl Rawvf {
+    /// Plays the events on a fresh game. Events after the end of the game and events
+    /// refused by the game are skipped.
+    pub fn game(&self) -> Game {
+        let mut game = Game::new(self.board.clone());
+        for event in &self.events {
+            if game.status().is_over() {
+                break;
+            }
+            let _ = game.play(event.mv);
+        }
+        game
+    }
+}
+
+/// Mouse buttons held down while reading events.
+#[derive(Debug, Default)]
+struct Buttons {
+    left: bool,
+    right: bool,
+    // Both buttons were held and the chord was already made by releasing one of them.
+    chorded: bool,
+}
+
+impl Buttons {
+    /// Updates the buttons with a mouse event and returns the kind of move it makes, if
+    /// any. Left release opens, right press flags, releasing one of both held buttons or
+    /// the middle button chords.
+    fn press(&mut self, kind: &str) -> Option<fn(Position) -> Move> {
+        match kind {
+            "lc" => {
+                self.left = true;
+                None
+            }
+            "rc" => {
+                self.right = true;
+                if self.left {
+                    None
+                } else {
+                    Some(Move::Flag)
+                }
+            }
+            "lr" | "rr" => {
+                let other = if kind == "lr" {
+                    self.left = false;
+                    self.right
+                } else {
+                    self.right = false;
+                    self.left
+                };
+                let mv: Option<fn(Position) -> Move> = match (other, self.chorded, kind) {
+                    (true, _, _) => {
+                        self.chorded = true;
+                        Some(Move::Chord)
+                    }
+                    (false, false, "lr") => Some(Move::Open),
+                    _ => None,
+                };
+                if !self.left && !self.right {
+                    self.chorded = false;
+                }
+                mv
+            }
+            "mr" => Some(Move::Chord),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a mouse event line such as `1.23 lr 9 5 (136 72)` into its time, kind and
+/// 0-based position, which is [`None`] outside the board. Returns [`None`] for lines
+/// which are not mouse events.
+#[allow(clippy::type_complexity)]
+fn parse_mouse_event(line: &str) -> Option<Result<(Duration, &str, Option<Position>), ()>> {
+    let mut words = line.split_whitespace();
+    let time = words.next()?;
+    let kind = words.next()?;
+    if !matches!(kind, "lc" | "lr" | "rc" | "rr" | "mc" | "mr" | "mv") {
+        return None;
+    }
+
+    let parse = || {
+        let seconds: f64 = time.parse().ok()?;
+        let time = Duration::from_millis((seconds * 1000.0).round().max(0.0) as u64);
+        // Square comes before the pixel coordinates in parentheses, which are ignored.
+        let mut square = words.map(|n| n.parse::<usize>().ok());
+        let (col, row) = (square.next()??, square.next()??);
+        let pos = (col.checked_sub(1))
+            .zip(row.checked_sub(1))
+            .map(|(x, y)| Position::new(x, y));
+        Some((time, kind, pos))
+    };
+    Some(parse().ok_or(()))
+}
+
+/// Imports a game from a RAWVF file. Header fields come before the `Board:` section,
+/// which has one line per row with `*` for mines and `0` for safe squares. Mouse events
+/// of the `Events:` section look like `time lc col row (px py)` with time in seconds,
+/// 1-based columns and rows and pixel coordinates, and are converted to moves. Other
+/// events are ignored. The `Mines:` header, if present, must match the mines of the board.
+///
+/// # Errors
+/// If the board section is missing or invalid, the mine count doesn't match the board or
+/// a mouse event can't be parsed.
+///
+/// # Examples
+/// ```
+/// use mineraker::formats::rawvf;
+/// use mineraker::game::{Move, Status};
+/// use mineraker::position::Position;
+///
+/// let text = "\
+/// RawVF_Version: Rev2
+/// Width: 3
+/// Height: 2
+/// Mines: 1
+/// Board:
+/// 00*
+/// 000
+/// Events:
+/// 0.00 start
+/// 0.00 lc 1 1 (8 8)
+/// 0.00 lr 1 1 (8 8)
+/// 1.25 rc 3 1 (40 8)
+/// 1.30 rr 3 1 (40 8)
+/// 1.50 lc 3 2 (40 24)
+/// 1.50 lr 3 2 (40 24)
+/// ";
+/// let imported = rawvf::import(text).unwrap();
+/// assert_eq!(imported.board.solution().to_string(), "_1*\n_11\n");
+/// assert_eq!(imported.events[1].mv, Move::Flag(Position::new(2, 0)));
+/// assert_eq!(imported.game().status(), Status::Won);
+/// ```
+pub fn import(text: &str) -> Result<Rawvf, RawvfError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+
+    let mut header = Vec::new();
+    let mut header_lines = Vec::new();
+    for (line, text) in lines.by_ref() {
+        if text == "Board:" {
+            break;
+        }
+        if let Some((key, value)) = text.split_once(':') {
+            header.push((key.trim().to_string(), value.trim().to_string()));
+            header_lines.push(line);
+        }
+    }
+    let header_number = |key: &'static str| {
+        header
+            .iter()
+            .zip(&header_lines)
+            .find(|((k, _), _)| k == key)
+            .map(|((_, v), &line)| {
+                v.parse::<usize>()
+                    .map(|n| (n, line))
+                    .map_err(|_| RawvfError {
+                        line,
+                        kind: RawvfErrorKind::InvalidHeader(key),
+                    })
+            })
+            .transpose()
+    };
+    let width = header_number("Width")?.map(|(width, _)| width);
+    let height = header_number("Height")?.map(|(height, _)| height);
+    let mine_count = header_number("Mines")?;
+
+    let mut mines = Vec::new();
+    let mut rows: Vec<usize> = Vec::new();
+    let mut board_width = width;
+    let mut last_line = 0;
+    for (line, text) in lines.by_ref() {
+        last_line = line;
+        if text.is_empty() || text.ends_with(':') {
+            break;
+        }
+        let expected = *board_width.get_or_insert(text.chars().count());
+        for (x, c) in text.chars().enumerate() {
+            match c {
+                '*' => mines.push(rows.len() * expected + x),
+                '0' => {}
+                _ => {
+                    return Err(RawvfError {
+                        line,
+                        kind: RawvfErrorKind::InvalidCharacter(c),
+                    })
+                }
+            }
+        }
+        let found = text.chars().count();
+        if found != expected {
+            return Err(RawvfError {
+                line,
+                kind: RawvfErrorKind::LineLength { expected, found },
+            });
+        }
+        rows.push(line);
+    }
+
+    let width = match board_width {
+        Some(width) if !rows.is_empty() && width > 0 => width,
+        _ => {
+            return Err(RawvfError {
+                line: last_line.max(1),
+                kind: RawvfErrorKind::MissingBoard,
+            })
+        }
+    };
+    if let Some(expected) = height.filter(|&height| height != rows.len()) {
+        return Err(RawvfError {
+            line: rows[0],
+            kind: RawvfErrorKind::LineCount {
+                expected,
+                found: rows.len(),
+            },
+        });
+    }
+    if let Some((expected, line)) = mine_count.filter(|&(count, _)| count != mines.len()) {
+        return Err(RawvfError {
+            line,
+            kind: RawvfErrorKind::MineCount {
+                expected,
+                found: mines.len(),
+            },
+        });
+    }
+    let board = Board::from_mines(Grid::new(width, rows.len(), Shape::Bounded), mines);
+
+    let mut events = Vec::new();
+    let mut buttons = Buttons::default();
+    for (line, text) in lines {
+        let (time, kind, pos) = match parse_mouse_event(text) {
+            None => continue,
+            Some(Ok(event)) => event,
+            Some(Err(())) => {
+                return Err(RawvfError {
+                    line,
+                    kind: RawvfErrorKind::InvalidEvent,
+                })
+            }
+        };
+        // Buttons are updated even when the mouse is outside the board, where moves do
+        // nothing.
+        if let (Some(mv), Some(pos)) = (buttons.press(kind), pos) {
+            if board.contains(pos) {
+                events.push(Event { time, mv: mv(pos) });
+            }
+        }
+    }
+
+    Ok(Rawvf {
+        header,
+        board,
+        events,
+    })
+}
+
+/// Exports a board and moves to a RAWVF file. Only the mines of the board are used.
+/// Opening is a left click, flagging a right click and chording a middle click.
+///
+/// # Errors
+/// If the board is not a bounded rectangle without a mask or its cells can hold several
+/// mines, see [`ExportError`].
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use mineraker::board::Board;
+/// use mineraker::formats::rawvf;
+/// use mineraker::game::Move;
+/// use mineraker::position::Position;
+/// use mineraker::replay::Event;
+///
+/// let board: Board = "_1*\n_11\n".parse().unwrap();
+/// let events = [Event {
+///     time: Duration::from_millis(1250),
+///     mv: Move::Open(Position::new(0, 1)),
+/// }];
+/// let text = rawvf::export(&board, &events).unwrap();
+/// assert!(text.contains("Board:\n00*\n000\n"));
+/// assert!(text.contains("1.250 lc 1 2 (8 24)\n1.250 lr 1 2 (8 24)\n"));
+///
+/// let imported = rawvf::import(&text).unwrap();
+/// assert_eq!(imported.events, events);
+/// ```
+pub fn export(board: &Board, events: &[Event]) -> Result<String, ExportError> {
+    if !board.topology().is_bounded_rectangle() {
+        return Err(ExportError::Shape);
+    }
+    if board.mines_per_cell() > 1 {
+        return Err(ExportError::MinesPerCell(board.mines_per_cell()));
+    }
+    let (width, height) = (board.width(), board.height());
+    let mine_count = board.mine_count();
+    let level = match (width, height, mine_count) {
+        (8, 8, 10) | (9, 9, 10) => "Beginner",
+        (16, 16, 40) => "Intermediate",
+        (30, 16, 99) => "Expert",
+        _ => "Custom",
+    };
+
+    let mut out = String::new();
+    out.push_str("RawVF_Version: Rev2\n");
+    out.push_str("Program: mineraker\n");
+    let _ = writeln!(out, "Level: {}", level);
+    let _ = writeln!(out, "Width: {}", width);
+    let _ = writeln!(out, "Height: {}", height);
+    let _ = writeln!(out, "Mines: {}", mine_count);
+    out.push_str("Marks: Off\n");
+
+    out.push_str("Board:\n");
+    for (pos, tile) in board.tiles_and_pos() {
+        out.push(if tile.value() == Value::Mine {
+            '*'
+        } else {
+            '0'
+        });
+        if pos.x + 1 == width {
+            out.push('\n');
+        }
+    }
+
+    out.push_str("Events:\n");
+    for event in events {
+        let pos = event.mv.pos();
+        let (press, release) = match event.mv {
+            Move::Open(_) => ("lc", "lr"),
+            Move::Flag(_) => ("rc", "rr"),
+            Move::Chord(_) => ("mc", "mr"),
+        };
+        let ms = event.time.as_millis();
+        for kind in [press, release] {
+            let _ = writeln!(
+                out,
+                "{}.{:03} {} {} {} ({} {})",
+                ms / 1000,
+                ms % 1000,
+                kind,
+                pos.x + 1,
+                pos.y + 1,
+                pos.x * SQUARE_SIZE + SQUARE_SIZE / 2,
+                pos.y * SQUARE_SIZE + SQUARE_SIZE / 2
+            );
+        }
+    }
+    Ok(out)
+}
+
+/// Exports the applied moves of the game's history to a RAWVF file. The history has no
+/// timing, so all events are at time zero.
+///
+/// # Errors
+/// If the board of the game can't be exported, see [`export`].
+pub fn export_game(game: &Game) -> Result<String, ExportError> {
+    let history = game.history();
+    let events: Vec<_> = history.entries()[..history.cursor()]
+        .iter()
+        .map(|entry| Event {
+            time: Duration::ZERO,
+            mv: entry.mv,
+        })
+        .collect();
+    export(game.board(), &events)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        board::{Board, GenerationSettings, Seed},
+        formats::rawvf::{export, export_game, import, ExportError, RawvfError, RawvfErrorKind},
+        game::{Game, Move},
+        position::Position,
+        replay::Event,
+        solver::Solver,
+        tile::State,
+        topology::{Grid, Mask, Shape},
+    };
+
+    #[test]
+    fn imports_chords_from_both_buttons() {
+        let text = "\
+Board:
+*00
+000
+000
+Events:
+0.10 lc 2 2 (24 24)
+0.20 lr 2 2 (24 24)
+0.30 rc 1 1 (8 8)
+0.40 rr 1 1 (8 8)
+0.50 lc 2 1 (24 8)
+0.55 rc 2 1 (24 8)
+0.60 rr 2 1 (24 8)
+0.70 lr 2 1 (24 8)
+0.80 mv 3 3 (40 40)
+0.90 mc 2 2 (24 24)
+1.00 mr 2 2 (24 24)
+1.10 won
+";
+        let moves: Vec<_> = import(text)
+            .unwrap()
+            .events
+            .iter()
+            .map(|event| event.mv)
+            .collect();
+
+        assert_eq!(
+            moves,
+            [
+                Move::Open(Position::new(1, 1)),
+                Move::Flag(Position::new(0, 0)),
+                Move::Chord(Position::new(1, 0)),
+                Move::Chord(Position::new(1, 1)),
+            ]
+        );
+    }
+
+    /// Start of a beginner game in the layout written by Minesweeper Arbiter, where the
+    /// square of an event comes before its pixels. Two mines are flagged and the number
+    /// next to both is chorded with the middle button.
+    const ARBITER_GAME: &str = "\
+RawVF_Version: Rev2
+Program: Minesweeper Arbiter 0.52.3
+Player: Anonymous
+Timestamp: 2024-03-02 18:41:07
+Level: Beginner
+Width: 8
+Height: 8
+Mines: 10
+Marks: Off
+Time: 4.21
+Board:
+*000000*
+00000000
+00000000
+000*0000
+00*0*000
+*0000000
+00000**0
+*0000*00
+Events:
+0.00 start
+0.00 lc 5 1 (72 8)
+0.00 lr 5 1 (72 8)
+1.02 mv 4 4 (56 56)
+1.19 rc 4 4 (56 56)
+1.27 rr 4 4 (56 56)
+1.55 rc 3 5 (40 72)
+1.63 rr 3 5 (40 72)
+1.96 mc 3 4 (42 57)
+2.08 mr 3 4 (42 57)
+";
+
+    #[test]
+    fn imports_arbiter_layout() {
+        let imported = import(ARBITER_GAME).unwrap();
+        let moves: Vec<_> = imported.events.iter().map(|event| event.mv).collect();
+
+        assert_eq!(imported.board.mine_count(), 10);
+        assert_eq!(
+            moves,
+            [
+                Move::Open(Position::new(4, 0)),
+                Move::Flag(Position::new(3, 3)),
+                Move::Flag(Position::new(2, 4)),
+                Move::Chord(Position::new(2, 3)),
+            ]
+        );
+        assert_eq!(imported.events[3].time, Duration::from_millis(2080));
+
+        // The chord opens the tile between the flags.
+        let game = imported.game();
+        assert_eq!(game.history().cursor(), 4);
+        assert_eq!(
+            game.board().get_tile(Position::new(3, 4)).unwrap().state(),
+            State::Open
+        );
+    }
+
+    #[test]
+    fn solved_game_round_trip() {
+        let gs = GenerationSettings {
+            seed: Seed::new(5),
+            width: 16,
+            height: 16,
+            mine_count: 40,
+            start_pos: Position::new(8, 8),
+            ..Default::default()
+        };
+        let mut game = Game::new(Board::with_empty_at(&gs, gs.start_pos).unwrap());
+        game.play(Move::Open(gs.start_pos)).unwrap();
+        let mut solver = Solver::new(game);
+        solver.solve();
+        let game = solver.into_game();
+
+        let imported = import(&export_game(&game).unwrap()).unwrap();
+        assert_eq!(
+            imported.board.solution().to_string(),
+            game.board().solution().to_string()
+        );
+        assert_eq!(imported.events.len(), game.history().cursor());
+        assert_eq!(
+            imported.game().board().to_string(),
+            game.board().to_string()
+        );
+        assert_eq!(imported.game().status(), game.status());
+    }
+
+    #[test]
+    fn times_have_millisecond_precision() {
+        let board: Board = "*1\n".parse().unwrap();
+        let events = [Event {
+            time: Duration::from_millis(61_005),
+            mv: Move::Open(Position::new(1, 0)),
+        }];
+        let text = export(&board, &events).unwrap();
+
+        assert!(text.contains("61.005 lr 2 1 (24 8)"));
+        assert_eq!(import(&text).unwrap().events, events);
+    }
+
+    #[test]
+    fn only_bounded_rectangles_are_exported() {
+        let gs = GenerationSettings {
+            width: 4,
+            height: 3,
+            mine_count: 2,
+            ..Default::default()
+        };
+        let mask: Mask = "##..\n####\n####\n".parse().unwrap();
+        let masked = Board::try_with_topology(Grid::masked(mask, Shape::Bounded), &gs).unwrap();
+        let torus = Board::new(&GenerationSettings {
+            shape: Shape::Torus,
+            ..gs
+        });
+        let stacked = Board::new(&GenerationSettings {
+            mines_per_cell: 2,
+            ..gs
+        });
+
+        assert_eq!(export(&masked, &[]), Err(ExportError::Shape));
+        assert_eq!(export(&torus, &[]), Err(ExportError::Shape));
+        assert_eq!(export(&stacked, &[]), Err(ExportError::MinesPerCell(2)));
+        assert!(export(&Board::new(&gs), &[]).is_ok());
+    }
+
+    #[test]
+    fn errors_point_to_line() {
+        assert_eq!(
+            import("Width: 2\nBoard:\n0*\n0x\n").unwrap_err(),
+            RawvfError {
+                line: 4,
+                kind: RawvfErrorKind::InvalidCharacter('x'),
+            }
+        );
+        assert_eq!(
+            import("Height: 3\nBoard:\n0*\n00\nEvents:\n").unwrap_err(),
+            RawvfError {
+                line: 3,
+                kind: RawvfErrorKind::LineCount {
+                    expected: 3,
+                    found: 2
+                },
+            }
+        );
+        assert_eq!(
+            import("Board:\n0*\nEvents:\n0.1 lc 1 1 (8 8)\nx lr 1 1 (8 8)\n").unwrap_err(),
+            RawvfError {
+                line: 5,
+                kind: RawvfErrorKind::InvalidEvent,
+            }
+        );
+        assert_eq!(
+            import("Width: 2\n").unwrap_err().kind,
+            RawvfErrorKind::MissingBoard
+        );
+        assert_eq!(
+            import("Width: 2\nMines: 2\nBoard:\n0*\n00\n").unwrap_err(),
+            RawvfError {
+                line: 2,
+                kind: RawvfErrorKind::MineCount {
+                    expected: 2,
+                    found: 1
+                },
+            }
+        );
+    }
+}
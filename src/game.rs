@@ -0,0 +1,568 @@
+use crate::{
+    board::{Board, BoardError, GenerationSettings},
+    history::{Entry, History, TileChange},
+    position::Position,
+    tile::{State, Value},
+    topology::{Grid, Topology},
+};
+
+/// [`Status`] of a [`Game`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// No moves have been made yet.
+    NotStarted,
+    Playing,
+    /// All safe tiles have been opened.
+    Won,
+    /// A mine was opened at `exploded_at`.
+    Lost {
+        exploded_at: Position,
+    },
+}
+
+impl Status {
+    /// Returns `true` if the game has been either won or lost.
+    #[inline]
+    pub fn is_over(self) -> bool {
+        matches!(self, Status::Won | Status::Lost { .. })
+    }
+}
+
+/// A single move made by the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    /// Opens a closed tile, cascading through empty tiles.
+    Open(Position),
+    /// Toggles flag on a closed or flagged tile.
+    Flag(Position),
+    /// Opens all unflagged neighbors of an open number tile whose flag count matches.
+    Chord(Position),
+}
+
+impl Move {
+    /// Returns the position the move targets.
+    #[inline]
+    pub fn pos(self) -> Position {
+        match self {
+            Move::Open(pos) | Move::Flag(pos) | Move::Chord(pos) => pos,
+        }
+    }
+}
+
+/// [`MoveResult`] describes the effect of a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveResult {
+    /// Positions of the tiles whose state changed.
+    pub changed: Vec<Position>,
+    /// Status of the game after the move.
+    pub status: Status,
+}
+
+/// [`GameError`] describes why a move was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    /// The game has already been won or lost.
+    GameOver,
+    /// Position of the move is outside the board.
+    OutOfBounds(Position),
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::GameOver => write!(f, "game is already over"),
+            GameError::OutOfBounds(pos) => {
+                write!(f, "position ({}, {}) is outside the board", pos.x, pos.y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// [`Game`] wraps a [`Board`] and keeps track of whether the game is won or lost.
+#[derive(Debug, Clone)]
+pub struct Game<T = Grid> {
+    board: Board<T>,
+    status: Status,
+    // Number of safe tiles which are not yet open.
+    closed_safe: usize,
+    history: History,
+}
+
+impl Game {
+    /// Creates a new game with a board generated from the given settings.
+    pub fn from_settings(settings: &GenerationSettings) -> Result<Self, BoardError> {
+        Board::try_new(settings).map(Self::new)
+    }
+}
+
+impl<T: Topology> Game<T> {
+    /// Creates a new game from the given board. Tiles already opened on the board
+    /// count towards winning the game, so a board with an open mine is already lost and a
+    /// board with all safe tiles open is already won.
+    pub fn new(board: Board<T>) -> Self {
+        let closed_safe = board
+            .tiles_and_pos()
+            .filter(|(_, tile)| tile.value() != Value::Mine && tile.state() != State::Open)
+            .count();
+        let open_mine = board
+            .tiles_and_pos()
+            .find(|(_, tile)| tile.value() == Value::Mine && tile.state() == State::Open);
+        let status = match open_mine {
+            Some((exploded_at, _)) => Status::Lost { exploded_at },
+            None if closed_safe == 0 => Status::Won,
+            None => Status::NotStarted,
+        };
+
+        Self {
+            board,
+            status,
+            closed_safe,
+            history: History::default(),
+        }
+    }
+
+    /// Makes the given move and returns which tiles changed. The move is recorded in the
+    /// [`History`] of the game, discarding any undone moves. Moves which change no tiles
+    /// are not recorded and leave the status as it was.
+    ///
+    /// # Errors
+    /// If the game is already over or the position is outside the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::{Board, GenerationSettings};
+    /// use mineraker::game::{Game, Move, Status};
+    /// use mineraker::position::Position;
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 10,
+    ///     ..Default::default()
+    /// };
+    /// let mut game = Game::new(Board::with_empty_at(&gs, Position::new(4, 4)).unwrap());
+    /// assert_eq!(game.status(), Status::NotStarted);
+    ///
+    /// let result = game.play(Move::Open(Position::new(4, 4))).unwrap();
+    /// assert!(result.changed.len() >= 9);
+    /// assert_ne!(result.status, Status::NotStarted);
+    /// ```
+    pub fn play(&mut self, mv: Move) -> Result<MoveResult, GameError> {
+        if self.status.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let pos = mv.pos();
+        if !self.board.contains(pos) {
+            return Err(GameError::OutOfBounds(pos));
+        }
+        let status_before = self.status;
+
+        // Flags are counted up on boards with several mines per cell, so the tile before a
+        // flag move is kept as it was.
+        let flagged = match mv {
+            Move::Flag(pos) => self.board.get_tile(pos).copied(),
+            _ => None,
+        };
+        let changed = match mv {
+            Move::Open(pos) => self.board.open_from(pos),
+            Move::Flag(pos) if self.board.flag_from(pos) => vec![pos],
+            Move::Flag(_) => Vec::new(),
+            Move::Chord(pos) => self.board.chord(pos).opened,
+        };
+        // Moves which change nothing don't start the game and aren't recorded.
+        if changed.is_empty() {
+            return Ok(MoveResult {
+                changed,
+                status: self.status,
+            });
+        }
+
+        if self.status == Status::NotStarted {
+            self.status = Status::Playing;
+        }
+        if !matches!(mv, Move::Flag(_)) {
+            self.update_status(&changed);
+        }
+
+        // Values never change, so tiles opened by the move can be derived from their
+        // current state, as they were closed.
+        let changes = changed
+            .iter()
+            .map(|&pos| {
+                let after = *self.board.get_tile(pos).unwrap();
+                TileChange {
+                    pos,
+                    before: flagged.unwrap_or_else(|| after.with_state(State::Closed)),
+                    after,
+                }
+            })
+            .collect();
+        self.history.push(Entry {
+            mv,
+            changes,
+            status_before,
+            status_after: self.status,
+        });
+
+        Ok(MoveResult {
+            changed,
+            status: self.status,
+        })
+    }
+
+    /// Undoes the last applied move and returns it, or [`None`] if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Move> {
+        let entry = self.history.step_back()?;
+        self.board.revert_changes(&entry.changes);
+        self.closed_safe += entry.changes.iter().filter(|c| c.opens_safe_tile()).count();
+        self.status = entry.status_before;
+
+        Some(entry.mv)
+    }
+
+    /// Redoes the last undone move and returns it, or [`None`] if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Move> {
+        let entry = self.history.step_forward()?;
+        self.board.apply_changes(&entry.changes);
+        self.closed_safe -= entry.changes.iter().filter(|c| c.opens_safe_tile()).count();
+        self.status = entry.status_after;
+
+        Some(entry.mv)
+    }
+
+    /// Undoes or redoes moves until exactly `moves` moves from the beginning of the
+    /// history are applied. Positions past the end of the history stop at the end.
+    pub fn seek(&mut self, moves: usize) {
+        while self.history.cursor() > moves && self.undo().is_some() {}
+        while self.history.cursor() < moves && self.redo().is_some() {}
+    }
+
+    /// Returns the history of moves.
+    #[inline]
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Updates the status after the given tiles were opened.
+    fn update_status(&mut self, opened: &[Position]) {
+        let (mines, safe): (Vec<Position>, Vec<Position>) = opened
+            .iter()
+            .partition(|&&pos| self.board.get_tile(pos).unwrap().value() == Value::Mine);
+
+        // Safe tiles opened by a losing move count as well, as undo adds them back.
+        self.closed_safe -= safe.len();
+        if let Some(&exploded_at) = mines.first() {
+            self.status = Status::Lost { exploded_at };
+        } else if self.closed_safe == 0 {
+            self.status = Status::Won;
+        }
+    }
+
+    /// Returns the current status of the game.
+    #[inline]
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Returns the board of the game.
+    #[inline]
+    pub fn board(&self) -> &Board<T> {
+        &self.board
+    }
+
+    /// Returns the number of safe tiles which are still closed or flagged.
+    #[inline]
+    pub fn closed_safe_count(&self) -> usize {
+        self.closed_safe
+    }
+
+    /// Returns the number of mines minus the number of flags, as shown by a mine counter.
+    /// Tiles with several flags count every flag. Can be negative if there are more flags
+    /// than mines.
+    pub fn mines_left(&self) -> isize {
+        let flags: usize = self
+            .board
+            .tiles_and_pos()
+            .map(|(_, tile)| tile.flags() as usize)
+            .sum();
+
+        self.board.mine_count() as isize - flags as isize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, GenerationSettings, Seed},
+        game::{Game, GameError, Move, Status},
+        position::Position,
+        tile::{State, Value},
+    };
+
+    fn game(seed: u64) -> Game {
+        let gs = GenerationSettings {
+            seed: Seed::new(seed),
+            width: 9,
+            height: 9,
+            mine_count: 10,
+            ..Default::default()
+        };
+        Game::new(Board::with_empty_at(&gs, Position::new(4, 4)).unwrap())
+    }
+
+    fn positions_where(game: &Game, f: impl Fn(Value) -> bool) -> Vec<Position> {
+        game.board()
+            .tiles_and_pos()
+            .filter_map(|(pos, tile)| f(tile.value()).then_some(pos))
+            .collect()
+    }
+
+    #[test]
+    fn opening_mine_loses() {
+        let mut game = game(0);
+        let mine = positions_where(&game, |v| v == Value::Mine)[0];
+
+        let result = game.play(Move::Open(mine)).unwrap();
+
+        assert_eq!(result.changed, [mine]);
+        assert_eq!(result.status, Status::Lost { exploded_at: mine });
+        assert_eq!(
+            game.play(Move::Open(Position::new(4, 4))),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn opening_all_safe_tiles_wins() {
+        let mut game = game(1);
+
+        for pos in positions_where(&game, |v| v != Value::Mine) {
+            if game.status() == Status::Won {
+                break;
+            }
+            let result = game.play(Move::Open(pos)).unwrap();
+            if game.closed_safe_count() > 0 {
+                assert_eq!(result.status, Status::Playing);
+            }
+        }
+
+        assert_eq!(game.status(), Status::Won);
+        assert_eq!(game.closed_safe_count(), 0);
+    }
+
+    #[test]
+    fn flags_block_opening() {
+        let mut game = game(2);
+        let pos = Position::new(0, 0);
+
+        assert_eq!(game.play(Move::Flag(pos)).unwrap().changed, [pos]);
+        assert_eq!(game.mines_left(), 9);
+        assert!(game.play(Move::Open(pos)).unwrap().changed.is_empty());
+        assert_eq!(game.board().get_tile(pos).unwrap().state(), State::Flag);
+    }
+
+    #[test]
+    fn unchanged_moves_are_not_recorded() {
+        let mut game = game(2);
+        let pos = Position::new(4, 4);
+
+        assert!(game.play(Move::Chord(pos)).unwrap().changed.is_empty());
+        assert_eq!(game.status(), Status::NotStarted);
+        assert!(game.history().entries().is_empty());
+
+        game.play(Move::Open(pos)).unwrap();
+        let result = game.play(Move::Open(pos)).unwrap();
+        assert!(result.changed.is_empty());
+        assert_eq!(result.status, Status::Playing);
+        assert_eq!(game.history().entries().len(), 1);
+        assert_eq!(game.undo(), Some(Move::Open(pos)));
+        assert_eq!(game.status(), Status::NotStarted);
+    }
+
+    #[test]
+    fn chord_with_wrong_flag_loses() {
+        let mut game = game(4);
+        game.play(Move::Open(Position::new(4, 4))).unwrap();
+
+        // Find an open number with a mine around it and flag a safe neighbor instead.
+        let board = game.board().clone();
+        let (number, wrong) = board
+            .tiles_and_pos()
+            .filter(|(_, tile)| tile.state() == State::Open && tile.value() == Value::Near(1))
+            .find_map(|(pos, _)| {
+                board
+                    .neighbors_tile_and_pos(pos)
+                    .find(|(_, tile)| tile.state() == State::Closed && tile.value() != Value::Mine)
+                    .map(|(wrong, _)| (pos, wrong))
+            })
+            .unwrap();
+
+        game.play(Move::Flag(wrong)).unwrap();
+        let result = game.play(Move::Chord(number)).unwrap();
+
+        assert!(matches!(result.status, Status::Lost { .. }));
+        assert!(!result.changed.is_empty());
+    }
+
+    #[test]
+    fn chord_with_correct_flags_opens_neighbors() {
+        let mut game = game(5);
+        game.play(Move::Open(Position::new(4, 4))).unwrap();
+
+        let board = game.board().clone();
+        let number = board
+            .tiles_and_pos()
+            .find(|(pos, tile)| {
+                tile.state() == State::Open
+                    && tile.value() != Value::Near(0)
+                    && board.neighbors_tile_and_pos(*pos).any(|(_, tile)| {
+                        tile.state() == State::Closed && tile.value() != Value::Mine
+                    })
+            })
+            .map(|(pos, _)| pos)
+            .unwrap();
+        for (pos, tile) in board.neighbors_tile_and_pos(number) {
+            if tile.value() == Value::Mine {
+                game.play(Move::Flag(pos)).unwrap();
+            }
+        }
+
+        let result = game.play(Move::Chord(number)).unwrap();
+
+        assert!(!result.changed.is_empty());
+        assert!(!matches!(result.status, Status::Lost { .. }));
+        assert!(game
+            .board()
+            .neighbors_tile_and_pos(number)
+            .all(|(_, tile)| tile.state() != State::Closed));
+    }
+
+    #[test]
+    fn undo_restores_lost_game() {
+        let mut game = game(6);
+        game.play(Move::Open(Position::new(4, 4))).unwrap();
+        let before = game.board().to_string();
+        let closed_safe = game.closed_safe_count();
+        let mine = positions_where(&game, |v| v == Value::Mine)[0];
+
+        game.play(Move::Open(mine)).unwrap();
+        assert_eq!(game.undo(), Some(Move::Open(mine)));
+
+        assert_eq!(game.status(), Status::Playing);
+        assert_eq!(game.board().to_string(), before);
+        assert_eq!(game.closed_safe_count(), closed_safe);
+    }
+
+    #[test]
+    fn finished_boards_start_finished() {
+        let opened: Board = "1*\n11\n\n1?\n11\n".parse().unwrap();
+        assert_eq!(Game::new(opened).status(), Status::Won);
+
+        let exploded: Board = "1*\n11\n\n1*\n##\n".parse().unwrap();
+        assert_eq!(
+            Game::new(exploded).status(),
+            Status::Lost {
+                exploded_at: Position::new(1, 0)
+            }
+        );
+    }
+
+    #[test]
+    fn losing_chord_counts_opened_safe_tiles() {
+        let board: Board = "*1_\n11_\n___\n\n###\n#1#\n##?\n".parse().unwrap();
+        let mut game = Game::new(board);
+        assert_eq!(game.closed_safe_count(), 7);
+
+        let result = game.play(Move::Chord(Position::new(1, 1))).unwrap();
+        let closed_safe = game
+            .board()
+            .tiles_and_pos()
+            .filter(|(_, tile)| tile.state() != State::Open && tile.value() != Value::Mine)
+            .count();
+        assert!(matches!(result.status, Status::Lost { .. }));
+        assert_eq!(game.closed_safe_count(), closed_safe);
+
+        game.undo();
+        assert_eq!(game.closed_safe_count(), 7);
+        game.redo();
+        assert_eq!(game.closed_safe_count(), closed_safe);
+    }
+
+    #[test]
+    fn redo_reaches_same_state() {
+        let mut game = game(7);
+        let moves = [
+            Move::Flag(Position::new(0, 0)),
+            Move::Open(Position::new(4, 4)),
+            Move::Flag(Position::new(0, 0)),
+            Move::Open(Position::new(8, 8)),
+        ];
+        let mut states = vec![(game.board().to_string(), game.status())];
+        for mv in moves {
+            if game.play(mv).is_ok() {
+                states.push((game.board().to_string(), game.status()));
+            }
+        }
+        let end = game.history().cursor();
+
+        for target in (0..=end).rev().chain(0..=end) {
+            game.seek(target);
+            assert_eq!((game.board().to_string(), game.status()), states[target]);
+        }
+        assert!(!game.history().can_redo());
+    }
+
+    #[test]
+    fn new_move_discards_undone_moves() {
+        let mut game = game(8);
+        game.play(Move::Flag(Position::new(0, 0))).unwrap();
+        game.play(Move::Flag(Position::new(1, 0))).unwrap();
+        game.undo();
+
+        game.play(Move::Flag(Position::new(2, 0))).unwrap();
+
+        assert_eq!(game.history().entries().len(), 2);
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.undo(), Some(Move::Flag(Position::new(2, 0))));
+    }
+
+    #[test]
+    fn out_of_bounds_moves_are_refused() {
+        let mut game = game(3);
+
+        assert_eq!(
+            game.play(Move::Open(Position::new(9, 0))),
+            Err(GameError::OutOfBounds(Position::new(9, 0)))
+        );
+        assert_eq!(game.status(), Status::NotStarted);
+    }
+
+    #[test]
+    fn stacked_flags_count_towards_mines_left() {
+        let mut game = Game::from_settings(&GenerationSettings {
+            width: 9,
+            height: 9,
+            mine_count: 30,
+            mines_per_cell: 3,
+            ..Default::default()
+        })
+        .unwrap();
+        let mine = positions_where(&game, |v| v == Value::Mine)[0];
+
+        for _ in 0..3 {
+            game.play(Move::Flag(mine)).unwrap();
+        }
+        assert_eq!(game.mines_left(), 27);
+        assert_eq!(game.undo(), Some(Move::Flag(mine)));
+        assert_eq!(game.board().get_tile(mine).unwrap().flags(), 2);
+        assert_eq!(game.mines_left(), 28);
+
+        game.redo();
+        game.play(Move::Flag(mine)).unwrap();
+        assert_eq!(game.board().get_tile(mine).unwrap().state(), State::Closed);
+        assert_eq!(game.undo(), Some(Move::Flag(mine)));
+        assert_eq!(game.board().get_tile(mine).unwrap().flags(), 3);
+    }
+}
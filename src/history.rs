@@ -0,0 +1,112 @@
+use crate::{
+    game::{Move, Status},
+    position::Position,
+    tile::{State, Tile, Value},
+};
+
+/// [`TileChange`] records the state of a single tile before and after a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileChange {
+    pub pos: Position,
+    pub before: Tile,
+    pub after: Tile,
+}
+
+impl TileChange {
+    /// Returns `true` if the change opened a safe tile.
+    #[inline]
+    pub fn opens_safe_tile(&self) -> bool {
+        self.before.state() != State::Open
+            && self.after.state() == State::Open
+            && self.after.value() != Value::Mine
+    }
+}
+
+/// [`Entry`] records a move, the tiles it changed and the game status around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub mv: Move,
+    pub changes: Vec<TileChange>,
+    pub status_before: Status,
+    pub status_after: Status,
+}
+
+/// [`History`] is a journal of moves which can be stepped back and forth. Entries after
+/// the cursor have been undone and can be redone, until a new entry is pushed.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::game::{Game, Move};
+/// use mineraker::position::Position;
+///
+/// let gs = GenerationSettings {
+///     width: 8,
+///     height: 8,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let mut game = Game::new(Board::with_empty_at(&gs, Position::new(4, 4)).unwrap());
+/// let start = game.board().to_string();
+///
+/// game.play(Move::Open(Position::new(4, 4))).unwrap();
+/// game.play(Move::Flag(Position::new(0, 0))).unwrap();
+/// assert_eq!(game.history().cursor(), 2);
+///
+/// game.seek(0);
+/// assert_eq!(game.board().to_string(), start);
+/// assert_eq!(game.redo(), Some(Move::Open(Position::new(4, 4))));
+/// assert_eq!(game.history().cursor(), 1);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<Entry>,
+    // Number of entries currently applied.
+    cursor: usize,
+}
+
+impl History {
+    /// Adds an entry after the cursor, discarding all undone entries.
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.truncate(self.cursor);
+        self.entries.push(entry);
+        self.cursor += 1;
+    }
+
+    /// Moves the cursor back by one and returns the entry to undo.
+    pub fn step_back(&mut self) -> Option<&Entry> {
+        self.cursor = self.cursor.checked_sub(1)?;
+        self.entries.get(self.cursor)
+    }
+
+    /// Moves the cursor forward by one and returns the entry to redo.
+    pub fn step_forward(&mut self) -> Option<&Entry> {
+        let entry = self.entries.get(self.cursor)?;
+        self.cursor += 1;
+        Some(entry)
+    }
+
+    /// Returns all entries, including the undone ones after the cursor.
+    #[inline]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the number of entries currently applied.
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns `true` if there is an entry to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    /// Returns `true` if there is an undone entry to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.entries.len()
+    }
+}
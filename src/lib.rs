@@ -1,4 +1,15 @@
 pub mod area;
+pub mod bitboard;
 pub mod board;
+pub mod formats;
+pub mod game;
+pub mod history;
+pub mod metrics;
+pub mod parse;
 pub mod position;
+pub mod probability;
+pub mod replay;
+pub mod simulation;
+pub mod solver;
 pub mod tile;
+pub mod topology;
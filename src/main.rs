@@ -1,11 +1,8 @@
-mod area;
-mod board;
-mod position;
-mod tile;
+mod cli;
 
-use crate::{
-    board::{Board, GenerationSettings, Seed},
-    position::Position,
+use cli::{
+    args::{self, Command},
+    commands, play,
 };
 
 /// * Determine the requirements for board solving.
@@ -17,25 +14,59 @@ use crate::{
 /// * Efficient algorithms using mainly iterators.
 ///
 /// * Create [`Board`]s using builder pattern as [`Board`] is current version of it
-/// is built once and then played.
-/// ?? How would play-time generated board fit into this?
+///   is built once and then played.
+///   ?? How would play-time generated board fit into this?
 ///
 /// Move logic components into their own crates.
 ///
 /// If Area::next_action produces `None`, then Area itself is unusable.
 /// Area has to be used with other areas.
 /// TODO: How to determine these areas?
-
 fn main() {
-    let mut board = Board::new(&GenerationSettings {
-        seed: Seed::new(0),
-        width: 30,
-        height: 16,
-        mine_count: 99,
-        start_pos: Position::default(),
-    });
+    let command = match args::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, args::USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    board.open_from(Position { x: 16, y: 13 });
+    let result = match command {
+        Command::Generate { settings, output } => commands::generate(&settings, output.as_deref()),
+        Command::Solve { input, start } => commands::solve(input.as_deref(), start),
+        Command::Play {
+            settings,
+            mask,
+            input,
+        } => input
+            .map(|path| commands::read_board(Some(&path)))
+            .transpose()
+            .and_then(|board| play::run(&settings, mask, board).map_err(|err| err.to_string())),
+        Command::Stats {
+            settings,
+            mask,
+            count,
+        } => commands::stats(&settings, mask, count),
+        Command::Bench {
+            settings,
+            mask,
+            count,
+        } => commands::bench(&settings, mask, count),
+        Command::Simulate {
+            settings,
+            mask,
+            seeds,
+            strategy,
+            threads,
+        } => commands::simulate(settings, mask, seeds, strategy, threads),
+        Command::Help => {
+            println!("{}", args::USAGE);
+            Ok(())
+        }
+    };
 
-    println!("{}", board);
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
 }
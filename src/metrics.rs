@@ -0,0 +1,299 @@
+use std::time::Duration;
+
+use crate::{
+    board::Board,
+    position::Position,
+    tile::{State, Tile, Value},
+    topology::Topology,
+};
+
+/// [`Metrics`] contains difficulty metrics of a board, used to rate boards in competitive
+/// Minesweeper. All metrics only depend on the mines of the board, not on tile states.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// Minimum number of left clicks needed to clear the board without chording.
+    pub three_bv: usize,
+    /// Number of connected areas of empty tiles.
+    pub openings: usize,
+    /// Number of connected groups of number tiles which don't border any opening.
+    pub islands: usize,
+    /// Greedy estimate of the number of clicks needed to clear the board when flagging
+    /// and chording are used.
+    pub zini: usize,
+}
+
+/// Calculates all metrics of the board.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::Board;
+/// use mineraker::metrics::{self, Metrics};
+///
+/// let board: Board = "1*1\n111\n___\n".parse().unwrap();
+/// assert_eq!(
+///     metrics::metrics(&board),
+///     Metrics {
+///         three_bv: 3,
+///         openings: 1,
+///         islands: 2,
+///         zini: 3,
+///     }
+/// );
+/// ```
+pub fn metrics<T: Topology>(board: &Board<T>) -> Metrics {
+    Metrics {
+        three_bv: three_bv(board),
+        openings: openings(board).len(),
+        islands: islands(board),
+        zini: zini(board),
+    }
+}
+
+fn is_empty(tile: &Tile) -> bool {
+    tile.value() == Value::Near(0)
+}
+
+fn borders_empty<T: Topology>(board: &Board<T>, pos: Position) -> bool {
+    board
+        .neighbors_tile_and_pos(pos)
+        .any(|(_, tile)| is_empty(tile))
+}
+
+/// Returns whether the tile is a number which doesn't border any empty tile, so it must
+/// be clicked on its own.
+fn is_isolated_number<T: Topology>(board: &Board<T>, pos: Position, tile: &Tile) -> bool {
+    matches!(tile.value(), Value::Near(n) if n > 0) && !borders_empty(board, pos)
+}
+
+/// Returns the empty tiles of each opening of the board.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::Board;
+/// use mineraker::metrics;
+///
+/// let board: Board = "__1*1__\n__111__\n".parse().unwrap();
+/// assert_eq!(metrics::openings(&board).len(), 2);
+/// ```
+pub fn openings<T: Topology>(board: &Board<T>) -> Vec<Vec<Position>> {
+    let mut seen = vec![false; board.width() * board.height()];
+    let mut openings = Vec::new();
+    for (pos, tile) in board.tiles_and_pos() {
+        if !is_empty(tile) || seen[pos.to_index(board.width())] {
+            continue;
+        }
+        let area = board.flood_area(pos, |_, tile| is_empty(tile));
+        for p in &area {
+            seen[p.to_index(board.width())] = true;
+        }
+        openings.push(area);
+    }
+    openings
+}
+
+/// Returns the 3BV of the board: every opening takes one click and every number which
+/// doesn't border an opening takes one click of its own.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::Board;
+/// use mineraker::metrics;
+///
+/// let board: Board = "1*1\n111\n___\n".parse().unwrap();
+/// assert_eq!(metrics::three_bv(&board), 3);
+/// ```
+pub fn three_bv<T: Topology>(board: &Board<T>) -> usize {
+    let isolated = board
+        .tiles_and_pos()
+        .filter(|&(pos, tile)| is_isolated_number(board, pos, tile))
+        .count();
+    openings(board).len() + isolated
+}
+
+/// Returns the number of islands of the board: connected groups of numbers which don't
+/// border any opening.
+pub fn islands<T: Topology>(board: &Board<T>) -> usize {
+    let mut seen = vec![false; board.width() * board.height()];
+    let mut islands = 0;
+    for (pos, tile) in board.tiles_and_pos() {
+        if seen[pos.to_index(board.width())] || !is_isolated_number(board, pos, tile) {
+            continue;
+        }
+        for p in board.flood_area(pos, |p, tile| is_isolated_number(board, p, tile)) {
+            seen[p.to_index(board.width())] = true;
+        }
+        islands += 1;
+    }
+    islands
+}
+
+/// Returns the ZiNi of the board, a greedy estimate of the clicks needed to clear it with
+/// flagging and chording. All openings are clicked first. Then the number with the best
+/// premium, the count of closed safe neighbors minus the flags and the chord it needs, is
+/// chorded as long as the premium is positive. Remaining numbers are clicked one by one.
+/// Tiles with several mines take a flag click per mine. The result is never larger than
+/// [`three_bv`].
+pub fn zini<T: Topology>(board: &Board<T>) -> usize {
+    // Play on a copy of the board with every tile closed.
+    let closed = board
+        .tiles_and_pos()
+        .map(|(_, tile)| tile.with_state(State::Closed))
+        .collect();
+    let mut board = Board::from_tiles(closed, board.topology().clone());
+
+    let mut clicks = 0;
+    for opening in openings(&board) {
+        board.open_from(opening[0]);
+        clicks += 1;
+    }
+
+    loop {
+        let best = board
+            .tiles_and_pos()
+            .filter(|(_, tile)| matches!(tile.value(), Value::Near(n) if n > 0))
+            .map(|(pos, tile)| {
+                let (mut gain, mut flags) = (0, 0);
+                for (_, n) in board.neighbors_tile_and_pos(pos) {
+                    match (n.value(), n.state()) {
+                        (Value::Mine, State::Closed) => flags += n.mines() as isize,
+                        (Value::Near(_), State::Closed) => gain += 1,
+                        _ => {}
+                    }
+                }
+                // Opening a closed number costs a click, but that click is needed to
+                // clear the number anyway.
+                (
+                    gain as isize - flags - 1,
+                    pos,
+                    tile.state() == State::Closed,
+                )
+            })
+            // Prefer the first tile in row-major order on equal premiums.
+            .fold(
+                None,
+                |best: Option<(isize, Position, bool)>, candidate| match best {
+                    Some(best) if best.0 >= candidate.0 => Some(best),
+                    _ => Some(candidate),
+                },
+            );
+
+        let Some((_, pos, is_closed)) = best.filter(|best| best.0 > 0) else {
+            break;
+        };
+        if is_closed {
+            board.open_from(pos);
+            clicks += 1;
+        }
+        let mines: Vec<_> = board
+            .neighbors_tile_and_pos(pos)
+            .filter(|(_, tile)| tile.value() == Value::Mine && tile.state() == State::Closed)
+            .map(|(p, tile)| (p, tile.mines()))
+            .collect();
+        for (mine, count) in mines {
+            for _ in 0..count {
+                board.flag_from(mine);
+                clicks += 1;
+            }
+        }
+        board.chord(pos);
+        clicks += 1;
+    }
+
+    clicks
+        + board
+            .tiles_and_pos()
+            .filter(|(_, tile)| tile.value() != Value::Mine && tile.state() != State::Open)
+            .count()
+}
+
+/// Returns 3BV per second, the usual measure of a player's speed.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use mineraker::metrics;
+///
+/// assert_eq!(metrics::three_bv_per_second(150, Duration::from_secs(60)), 2.5);
+/// assert_eq!(metrics::three_bv_per_second(150, Duration::ZERO), 0.0);
+/// ```
+pub fn three_bv_per_second(three_bv: usize, time: Duration) -> f64 {
+    if time.is_zero() {
+        0.0
+    } else {
+        three_bv as f64 / time.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, GenerationSettings, Seed},
+        metrics::{islands, metrics, openings, three_bv, zini, Metrics},
+    };
+
+    #[test]
+    fn board_without_openings() {
+        let board: Board = "1*1\n111\n".parse().unwrap();
+
+        assert_eq!(
+            metrics(&board),
+            Metrics {
+                three_bv: 5,
+                openings: 0,
+                islands: 1,
+                // Open the bottom middle, flag the mine and chord.
+                zini: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn separate_islands() {
+        let board: Board = "\
+1*1___
+111___
+______
+___111
+___1*1
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(openings(&board).len(), 1);
+        assert_eq!(islands(&board), 2);
+        assert_eq!(three_bv(&board), 3);
+    }
+
+    #[test]
+    fn metrics_ignore_tile_states() {
+        let gs = GenerationSettings {
+            seed: Seed::new(9),
+            width: 16,
+            height: 16,
+            mine_count: 40,
+            ..Default::default()
+        };
+        let mut board = Board::new(&gs);
+        let before = metrics(&board);
+        board.open_from(gs.start_pos);
+
+        assert_eq!(metrics(&board), before);
+    }
+
+    #[test]
+    fn zini_never_exceeds_three_bv() {
+        for seed in 0..20 {
+            let board = Board::new(&GenerationSettings {
+                seed: Seed::new(seed),
+                width: 30,
+                height: 16,
+                mine_count: 99,
+                ..Default::default()
+            });
+            let zini = zini(&board);
+
+            assert!(zini > 0);
+            assert!(zini <= three_bv(&board));
+        }
+    }
+}
@@ -0,0 +1,605 @@
+use std::str::FromStr;
+
+use crate::{
+    board::Board,
+    position::Position,
+    tile::{Tile, Value},
+    topology::{Grid, Mask, Shape},
+};
+
+/// [`ParseErrorKind`] describes what went wrong when parsing a [`Board`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Input contains no tiles.
+    Empty,
+    /// Character doesn't represent a tile in this layout.
+    InvalidCharacter(char),
+    /// Line has a different number of tiles than the first line of its layout.
+    LineLength { expected: usize, found: usize },
+    /// Player view has a different number of lines than the solution above it.
+    LineCount { expected: usize, found: usize },
+    /// Number doesn't match the number of mines around the tile.
+    WrongNumber { expected: u8, found: u8 },
+    /// Number of a player view without a solution counts mines under closed tiles, which
+    /// can't be placed. Only flagged tiles and open mines are known to be mines.
+    HiddenMines { known: u8, found: u8 },
+    /// Open tile of the player view differs from the solution above it.
+    SolutionMismatch { expected: Value, found: Value },
+    /// There are more than two layouts separated by empty lines.
+    TooManyLayouts,
+}
+
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "board has no tiles"),
+            ParseErrorKind::InvalidCharacter(c) => write!(f, "invalid tile character {:?}", c),
+            ParseErrorKind::LineLength { expected, found } => {
+                write!(
+                    f,
+                    "expected {} tiles on the line, found {}",
+                    expected, found
+                )
+            }
+            ParseErrorKind::LineCount { expected, found } => write!(
+                f,
+                "expected {} lines in the player view, found {}",
+                expected, found
+            ),
+            ParseErrorKind::WrongNumber { expected, found } => write!(
+                f,
+                "tile has {} mines around it but shows {}",
+                expected, found
+            ),
+            ParseErrorKind::HiddenMines { known, found } => write!(
+                f,
+                "tile shows {} but only {} mines around it are known without a solution",
+                found, known
+            ),
+            ParseErrorKind::SolutionMismatch { expected, found } => write!(
+                f,
+                "open tile shows {} but the solution has {}",
+                found, expected
+            ),
+            ParseErrorKind::TooManyLayouts => {
+                write!(f, "expected at most a solution and a player view")
+            }
+        }
+    }
+}
+
+/// [`ParseBoardError`] is returned when a [`Board`] can't be parsed from text. Line and
+/// column are 1-based and point to the offending character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBoardError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl std::fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+/// Single character of a layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Value(Value),
+    Closed,
+    Flag,
+}
+
+/// Number of a tile character, `1` to `9` and then `a` to `s` up to [`Tile::MAX_NUMBER`],
+/// as numbers are displayed by [`Value`].
+fn number(c: char) -> Option<u8> {
+    c.to_digit(36)
+        .filter(|_| !c.is_ascii_uppercase())
+        .map(|n| n as u8)
+        .filter(|n| (1..=Tile::MAX_NUMBER).contains(n))
+}
+
+/// Cells of a layout in row-major order with their line and column numbers.
+struct Layout {
+    cells: Vec<(Cell, usize, usize)>,
+    width: usize,
+    first_line: usize,
+}
+
+impl Layout {
+    fn parse(lines: &[(usize, &str)]) -> Result<Self, ParseBoardError> {
+        let mut cells = Vec::new();
+        let mut width = None;
+        for &(line, text) in lines {
+            let mut count = 0;
+            for (i, c) in text.chars().enumerate() {
+                let cell = match c {
+                    '#' => Cell::Closed,
+                    '?' => Cell::Flag,
+                    '_' => Cell::Value(Value::Near(0)),
+                    '*' => Cell::Value(Value::Mine),
+                    _ => match number(c) {
+                        Some(n) => Cell::Value(Value::Near(n)),
+                        None => {
+                            return Err(ParseBoardError {
+                                line,
+                                column: i + 1,
+                                kind: ParseErrorKind::InvalidCharacter(c),
+                            })
+                        }
+                    },
+                };
+                cells.push((cell, line, i + 1));
+                count += 1;
+            }
+
+            let expected = *width.get_or_insert(count);
+            if count != expected {
+                return Err(ParseBoardError {
+                    line,
+                    column: count.min(expected) + 1,
+                    kind: ParseErrorKind::LineLength {
+                        expected,
+                        found: count,
+                    },
+                });
+            }
+        }
+
+        Ok(Self {
+            cells,
+            width: width.unwrap_or_default(),
+            first_line: lines[0].0,
+        })
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    fn is_player_view(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|(cell, _, _)| matches!(cell, Cell::Closed | Cell::Flag))
+    }
+}
+
+/// Calculates tile values from mine positions and checks them against the numbers given
+/// in the layout. Cells accepted by `is_unknown` may hide mines which aren't in the
+/// layout, so a number with more mines than known around it is only checked if none of
+/// its neighbors is unknown.
+fn tiles_from_mines(
+    layout: &Layout,
+    is_mine: impl Fn(usize) -> bool,
+    is_unknown: impl Fn(usize) -> bool,
+) -> Result<Vec<Tile>, ParseBoardError> {
+    let (width, height) = (layout.width, layout.height());
+    let mut tiles: Vec<Tile> = (0..layout.cells.len())
+        .map(|i| {
+            if is_mine(i) {
+                Tile::with_value(Value::Mine)
+            } else {
+                Tile::default()
+            }
+        })
+        .collect();
+    for i in (0..tiles.len()).filter(|&i| is_mine(i)) {
+        for pos in Position::from_index(i, width).neighbors(width, height) {
+            tiles[pos.to_index(width)].increment_value();
+        }
+    }
+
+    for (i, &(cell, line, column)) in layout.cells.iter().enumerate() {
+        if let (Cell::Value(Value::Near(found)), Value::Near(expected)) = (cell, tiles[i].value()) {
+            if found != expected {
+                let hidden = found > expected
+                    && Position::from_index(i, width)
+                        .neighbors(width, height)
+                        .any(|pos| is_unknown(pos.to_index(width)));
+                let kind = if hidden {
+                    ParseErrorKind::HiddenMines {
+                        known: expected,
+                        found,
+                    }
+                } else {
+                    ParseErrorKind::WrongNumber { expected, found }
+                };
+                return Err(ParseBoardError { line, column, kind });
+            }
+        }
+    }
+
+    Ok(tiles)
+}
+
+/// Parses a board in the text format produced by [`Board::solution`] and by [`Display`]
+/// for [`Board`]. Tiles are `_` for empty, `1` to `8` for numbers, `*` for mines, `#` for
+/// closed and `?` for flagged tiles. Boards are bounded grids with one mine per cell, so
+/// boards with other shapes, masks or stacked mines can't be parsed back. Three layouts
+/// are accepted:
+///
+/// * Solution layout, which only contains values. All tiles of the board are closed.
+/// * Player view layout, which contains closed or flagged tiles. Flagged tiles and open
+///   mines are the mines of the board, closed tiles are safe. Numbers counting mines
+///   under closed tiles can't be checked and need a solution layout.
+/// * Solution layout followed by an empty line and a player view layout of the same size.
+///   Mines come from the solution and tile states from the view, so the view can have
+///   mines which are neither flagged nor open.
+///
+/// Numbers are checked to match the mines around them.
+///
+/// [`Display`]: std::fmt::Display
+///
+/// # Examples
+/// ```
+/// use mineraker::board::Board;
+/// use mineraker::parse::{ParseBoardError, ParseErrorKind};
+/// use mineraker::position::Position;
+/// use mineraker::tile::State;
+///
+/// let board: Board = "1*1\n111\n\n1##\n#1#\n".parse().unwrap();
+/// assert_eq!(board.get_tile(Position::new(0, 0)).unwrap().state(), State::Open);
+/// assert_eq!(board.to_string(), "1##\n#1#\n");
+///
+/// let err = "1*2\n111\n".parse::<Board>().unwrap_err();
+/// assert_eq!(
+///     err,
+///     ParseBoardError {
+///         line: 1,
+///         column: 3,
+///         kind: ParseErrorKind::WrongNumber { expected: 1, found: 2 },
+///     }
+/// );
+/// ```
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split lines into layouts separated by empty lines.
+        let mut layouts: Vec<Vec<(usize, &str)>> = vec![Vec::new()];
+        for (i, text) in s.lines().enumerate() {
+            let text = text.trim_end();
+            match (text.is_empty(), layouts.last().unwrap().is_empty()) {
+                (true, true) => {}
+                (true, false) => layouts.push(Vec::new()),
+                (false, _) => layouts.last_mut().unwrap().push((i + 1, text)),
+            }
+        }
+        layouts.retain(|lines| !lines.is_empty());
+
+        let layouts = layouts
+            .iter()
+            .map(|lines| Layout::parse(lines))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match layouts.as_slice() {
+            [] => Err(ParseBoardError {
+                line: 1,
+                column: 1,
+                kind: ParseErrorKind::Empty,
+            }),
+            [layout] if layout.is_player_view() => {
+                let is_mine =
+                    |i: usize| matches!(layout.cells[i].0, Cell::Flag | Cell::Value(Value::Mine));
+                let is_closed = |i: usize| layout.cells[i].0 == Cell::Closed;
+                let mut tiles = tiles_from_mines(layout, is_mine, is_closed)?;
+                for (tile, &(cell, _, _)) in tiles.iter_mut().zip(&layout.cells) {
+                    match cell {
+                        Cell::Value(_) => tile.open(),
+                        Cell::Flag => tile.flag(),
+                        Cell::Closed => {}
+                    }
+                }
+                Ok(Board::from_tiles(
+                    tiles,
+                    Grid::new(layout.width, layout.height(), Shape::Bounded),
+                ))
+            }
+            [solution] => {
+                let is_mine = |i: usize| solution.cells[i].0 == Cell::Value(Value::Mine);
+                let tiles = tiles_from_mines(solution, is_mine, |_| false)?;
+                Ok(Board::from_tiles(
+                    tiles,
+                    Grid::new(solution.width, solution.height(), Shape::Bounded),
+                ))
+            }
+            [solution, view] => {
+                if let Some(&(cell, line, column)) = solution
+                    .cells
+                    .iter()
+                    .find(|(cell, _, _)| matches!(cell, Cell::Closed | Cell::Flag))
+                {
+                    let c = if cell == Cell::Flag { '?' } else { '#' };
+                    return Err(ParseBoardError {
+                        line,
+                        column,
+                        kind: ParseErrorKind::InvalidCharacter(c),
+                    });
+                }
+                if view.width != solution.width {
+                    return Err(ParseBoardError {
+                        line: view.first_line,
+                        column: view.width.min(solution.width) + 1,
+                        kind: ParseErrorKind::LineLength {
+                            expected: solution.width,
+                            found: view.width,
+                        },
+                    });
+                }
+                if view.height() != solution.height() {
+                    return Err(ParseBoardError {
+                        line: view.first_line,
+                        column: 1,
+                        kind: ParseErrorKind::LineCount {
+                            expected: solution.height(),
+                            found: view.height(),
+                        },
+                    });
+                }
+
+                let is_mine = |i: usize| solution.cells[i].0 == Cell::Value(Value::Mine);
+                let mut tiles = tiles_from_mines(solution, is_mine, |_| false)?;
+                for (tile, &(cell, line, column)) in tiles.iter_mut().zip(&view.cells) {
+                    match cell {
+                        Cell::Value(found) if found != tile.value() => {
+                            return Err(ParseBoardError {
+                                line,
+                                column,
+                                kind: ParseErrorKind::SolutionMismatch {
+                                    expected: tile.value(),
+                                    found,
+                                },
+                            })
+                        }
+                        Cell::Value(_) => tile.open(),
+                        Cell::Flag => tile.flag(),
+                        Cell::Closed => {}
+                    }
+                }
+                Ok(Board::from_tiles(
+                    tiles,
+                    Grid::new(solution.width, solution.height(), Shape::Bounded),
+                ))
+            }
+            [_, _, third, ..] => Err(ParseBoardError {
+                line: third.first_line,
+                column: 1,
+                kind: ParseErrorKind::TooManyLayouts,
+            }),
+        }
+    }
+}
+
+/// Parses a [`Mask`] from rows of characters. Spaces and `.` are outside the mask, and
+/// every tile character of the board text format is a cell, so the [`Display`] of a board
+/// can be used as its mask. Mask is as wide as its longest row, shorter rows are padded
+/// with positions outside the mask and empty lines at the end are ignored.
+///
+/// [`Display`]: std::fmt::Display
+impl FromStr for Mask {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = Vec::new();
+        for (i, text) in s.lines().enumerate() {
+            let mut row = Vec::new();
+            for (column, c) in text.chars().enumerate() {
+                row.push(match c {
+                    ' ' | '.' => false,
+                    '#' | '?' | '_' | '*' => true,
+                    _ if number(c).is_some() => true,
+                    _ => {
+                        return Err(ParseBoardError {
+                            line: i + 1,
+                            column: column + 1,
+                            kind: ParseErrorKind::InvalidCharacter(c),
+                        })
+                    }
+                });
+            }
+            rows.push(row);
+        }
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+        let mask = Mask::from_fn(width, rows.len(), |pos| {
+            rows[pos.y].get(pos.x).copied().unwrap_or_default()
+        });
+        if mask.cell_count() == 0 {
+            return Err(ParseBoardError {
+                line: 1,
+                column: 1,
+                kind: ParseErrorKind::Empty,
+            });
+        }
+        Ok(mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, GenerationSettings, Seed},
+        parse::{ParseBoardError, ParseErrorKind},
+        position::Position,
+        tile::{State, Value},
+        topology::Mask,
+    };
+
+    #[test]
+    fn solution_round_trip() {
+        for seed in 0..10 {
+            let board = Board::new(&GenerationSettings {
+                seed: Seed::new(seed),
+                width: 30,
+                height: 16,
+                mine_count: 99,
+                ..Default::default()
+            });
+            let text = board.solution().to_string();
+            let parsed: Board = text.parse().unwrap();
+
+            assert_eq!(parsed.solution().to_string(), text);
+            assert!(parsed
+                .tiles_and_pos()
+                .all(|(_, tile)| tile.state() == State::Closed));
+        }
+    }
+
+    #[test]
+    fn stacked_round_trip() {
+        let mut board = Board::new(&GenerationSettings {
+            seed: Seed::new(3),
+            width: 16,
+            height: 16,
+            mine_count: 40,
+            start_pos: Position::new(8, 8),
+            ..Default::default()
+        });
+        board.open_from(Position::new(8, 8));
+        board.flag_from(Position::new(0, 0));
+
+        let text = format!("{}\n{}", board.solution(), board);
+        let parsed: Board = text.parse().unwrap();
+
+        assert_eq!(parsed.to_string(), board.to_string());
+        assert_eq!(parsed.solution().to_string(), board.solution().to_string());
+    }
+
+    #[test]
+    fn player_view_infers_mines_from_flags() {
+        let board: Board = "?1_\n11_\n".parse().unwrap();
+
+        assert_eq!(
+            board.get_tile(Position::new(0, 0)).unwrap().value(),
+            Value::Mine
+        );
+        assert_eq!(
+            board.get_tile(Position::new(0, 0)).unwrap().state(),
+            State::Flag
+        );
+        assert_eq!(board.to_string(), "?1_\n11_\n");
+    }
+
+    #[test]
+    fn player_view_numbers_are_checked_against_known_mines() {
+        let err = |s: &str| s.parse::<Board>().unwrap_err().kind;
+
+        // Closed neighbors are safe if the known mines already match the number.
+        let board: Board = "?1#\n11#\n".parse().unwrap();
+        assert_eq!(board.to_string(), "?1#\n11#\n");
+
+        assert_eq!(
+            err("1#\n##\n"),
+            ParseErrorKind::HiddenMines { known: 0, found: 1 }
+        );
+        assert_eq!(
+            err("?1\n?#\n"),
+            ParseErrorKind::WrongNumber {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            err("12\n?1\n"),
+            ParseErrorKind::WrongNumber {
+                expected: 1,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn errors_point_to_character() {
+        let err = |s: &str| s.parse::<Board>().unwrap_err();
+
+        assert_eq!(
+            err("__\n_x\n"),
+            ParseBoardError {
+                line: 2,
+                column: 2,
+                kind: ParseErrorKind::InvalidCharacter('x'),
+            }
+        );
+        assert_eq!(err("_t\n").kind, ParseErrorKind::InvalidCharacter('t'));
+        assert_eq!(
+            err("_\n9\n"),
+            ParseBoardError {
+                line: 2,
+                column: 1,
+                kind: ParseErrorKind::WrongNumber {
+                    expected: 0,
+                    found: 9
+                },
+            }
+        );
+        assert_eq!(
+            err("___\n__\n"),
+            ParseBoardError {
+                line: 2,
+                column: 3,
+                kind: ParseErrorKind::LineLength {
+                    expected: 3,
+                    found: 2
+                },
+            }
+        );
+        assert_eq!(
+            err("1*\n11\n\n1#\n"),
+            ParseBoardError {
+                line: 4,
+                column: 1,
+                kind: ParseErrorKind::LineCount {
+                    expected: 2,
+                    found: 1
+                },
+            }
+        );
+        assert_eq!(
+            err("1*\n11\n\n2#\n##\n"),
+            ParseBoardError {
+                line: 4,
+                column: 1,
+                kind: ParseErrorKind::SolutionMismatch {
+                    expected: Value::Near(1),
+                    found: Value::Near(2)
+                },
+            }
+        );
+        assert_eq!(
+            err("\n\n"),
+            ParseBoardError {
+                line: 1,
+                column: 1,
+                kind: ParseErrorKind::Empty,
+            }
+        );
+    }
+
+    #[test]
+    fn masks_from_rows_and_board_text() {
+        // Trailing blanks of the board text are often trimmed, rows are padded back.
+        let mask: Mask = " #1\n?_*8\n\n".parse().unwrap();
+        assert_eq!(mask.to_string(), ".##.\n####\n");
+        // Cells of three-dimensional boards can have more than 8 mines around them.
+        let mask: Mask = "9as\n".parse().unwrap();
+        assert_eq!(mask.cell_count(), 3);
+
+        let err = "#.\n#x\n".parse::<Mask>().unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.kind, ParseErrorKind::InvalidCharacter('x'));
+        assert_eq!(
+            "..\n  \n".parse::<Mask>().unwrap_err().kind,
+            ParseErrorKind::Empty
+        );
+    }
+}
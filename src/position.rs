@@ -1,6 +1,7 @@
 /// [`Position`] stores 2-dimensional non-negative coordinates in uniform grid space,
 /// or xy-coordinates.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -26,6 +27,21 @@ impl Position {
         }
     }
 
+    /// Converts index into [`Position`] in row-major order like [`Position::from_index`],
+    /// but returns [`None`] instead of panicking if `width == 0`.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::position::Position;
+    ///
+    /// assert_eq!(Position::checked_from_index(9, 4), Some(Position::new(1, 2)));
+    /// assert_eq!(Position::checked_from_index(9, 0), None);
+    /// ```
+    #[inline]
+    pub fn checked_from_index(index: usize, width: usize) -> Option<Self> {
+        (width != 0).then(|| Self::from_index(index, width))
+    }
+
     /// Converts [`Position`] into index in row-major order, where
     /// width is the width of each row.
     #[inline]
@@ -87,3 +103,87 @@ impl From<(usize, usize)> for Position {
         Self { x: xy.0, y: xy.1 }
     }
 }
+
+/// [`Point`] stores N-dimensional non-negative coordinates, such as xyz-coordinates of a
+/// three-dimensional board. Indexes of points are in row-major order with the first
+/// coordinate changing fastest, so the index of a two-dimensional point is the index of
+/// the [`Position`] with the same coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point<const N: usize>(pub [usize; N]);
+
+impl<const N: usize> Point<N> {
+    /// Creates a new point at given coordinates.
+    #[inline]
+    pub fn new(coords: [usize; N]) -> Self {
+        Self(coords)
+    }
+
+    /// Converts index into [`Point`] inside a box of the given dimensions.
+    ///
+    /// # Panics
+    /// if any dimension is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::position::Point;
+    ///
+    /// assert_eq!(Point::from_index(17, [4, 3, 2]), Point::new([1, 1, 1]));
+    /// assert_eq!(Point::new([1, 1, 1]).to_index([4, 3, 2]), 17);
+    /// ```
+    pub fn from_index(mut index: usize, dims: [usize; N]) -> Self {
+        let mut coords = [0; N];
+        for (c, len) in coords.iter_mut().zip(dims) {
+            *c = index % len;
+            index /= len;
+        }
+        Self(coords)
+    }
+
+    /// Converts [`Point`] into index inside a box of the given dimensions.
+    pub fn to_index(self, dims: [usize; N]) -> usize {
+        self.0
+            .iter()
+            .zip(dims)
+            .rev()
+            .fold(0, |index, (&c, len)| index * len + c)
+    }
+
+    /// Returns iterator over the `3^N - 1` neighbor [`Point`]s of the point, which differ
+    /// from it by at most one in every coordinate. Filters out points which are outside
+    /// the given dimensions. Points are returned in the order of their indexes, which
+    /// for two dimensions is the order of [`Position::neighbors`].
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::position::Point;
+    ///
+    /// assert_eq!(Point::new([1, 1, 1]).neighbors([3, 3, 3]).count(), 26);
+    /// assert_eq!(Point::new([0, 0, 0]).neighbors([3, 3, 3]).count(), 7);
+    /// ```
+    pub fn neighbors(self, dims: [usize; N]) -> impl Iterator<Item = Self> {
+        let count = 3usize.pow(N as u32);
+        // Offset with every digit one in base three is the point itself.
+        (0..count)
+            .filter(move |&offset| offset != count / 2)
+            .filter_map(move |mut offset| {
+                let mut coords = self.0;
+                for (c, len) in coords.iter_mut().zip(dims) {
+                    // Use wrapping_sub to wrap around to usize::MAX on zero values to always
+                    // filter them out.
+                    *c = c.wrapping_add(offset % 3).wrapping_sub(1);
+                    offset /= 3;
+                    if *c >= len {
+                        return None;
+                    }
+                }
+                Some(Self(coords))
+            })
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for Point<N> {
+    /// Convert array of coordinates into a point.
+    fn from(coords: [usize; N]) -> Self {
+        Self(coords)
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    area::Area,
+    board::Board,
+    position::Position,
+    tile::{State, Value},
+    topology::Topology,
+};
+
+/// Mine configurations of one frontier component, grouped by the number of mines.
+struct Configurations {
+    /// Number of valid configurations with `k` mines at index `k`.
+    counts: Vec<f64>,
+    /// Number of valid configurations with `k` mines at index `k` where the cell at
+    /// the inner index is a mine.
+    hits: Vec<Vec<f64>>,
+}
+
+/// Frontier cells connected through shared constraints, with the constraints given
+/// as indexes to `cells`.
+struct Component {
+    cells: Vec<Position>,
+    constraints: Vec<(Vec<usize>, usize)>,
+}
+
+impl Component {
+    /// Enumerates every mine configuration of the component which satisfies all of
+    /// its constraints.
+    fn enumerate(&self) -> Configurations {
+        let mut cell_constraints = vec![Vec::new(); self.cells.len()];
+        for (c, (cells, _)) in self.constraints.iter().enumerate() {
+            for &cell in cells {
+                cell_constraints[cell].push(c);
+            }
+        }
+
+        let mut search = Search {
+            component: self,
+            cell_constraints,
+            mines: self.constraints.iter().map(|_| 0).collect(),
+            unassigned: self.constraints.iter().map(|(c, _)| c.len()).collect(),
+            assignment: vec![false; self.cells.len()],
+            result: Configurations {
+                counts: vec![0.0; self.cells.len() + 1],
+                hits: vec![vec![0.0; self.cells.len()]; self.cells.len() + 1],
+            },
+        };
+        search.assign(0, 0);
+
+        search.result
+    }
+}
+
+/// State of the backtracking search over the cells of a [`Component`].
+struct Search<'a> {
+    component: &'a Component,
+    cell_constraints: Vec<Vec<usize>>,
+    // Number of mines assigned to each constraint so far.
+    mines: Vec<usize>,
+    // Number of cells of each constraint without an assignment.
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    result: Configurations,
+}
+
+impl Search<'_> {
+    fn assign(&mut self, cell: usize, mine_count: usize) {
+        if cell == self.assignment.len() {
+            self.result.counts[mine_count] += 1.0;
+            for (i, _) in self.assignment.iter().enumerate().filter(|(_, &m)| m) {
+                self.result.hits[mine_count][i] += 1.0;
+            }
+            return;
+        }
+
+        for is_mine in [false, true] {
+            self.assignment[cell] = is_mine;
+            for &c in &self.cell_constraints[cell] {
+                self.unassigned[c] -= 1;
+                self.mines[c] += is_mine as usize;
+            }
+
+            let feasible = self.cell_constraints[cell].iter().all(|&c| {
+                let required = self.component.constraints[c].1;
+                self.mines[c] <= required && self.mines[c] + self.unassigned[c] >= required
+            });
+            if feasible {
+                self.assign(cell + 1, mine_count + is_mine as usize);
+            }
+
+            for &c in &self.cell_constraints[cell] {
+                self.unassigned[c] += 1;
+                self.mines[c] -= is_mine as usize;
+            }
+        }
+        self.assignment[cell] = false;
+    }
+}
+
+/// Splits the constraint areas into components which share no cells. Cells are ordered
+/// so that cells of the same constraint are close to each other, which keeps the
+/// backtracking search shallow.
+fn components(areas: &[Area]) -> Vec<Component> {
+    let mut areas_at: HashMap<Position, Vec<usize>> = HashMap::new();
+    for (i, area) in areas.iter().enumerate() {
+        for &pos in area.positions() {
+            areas_at.entry(pos).or_default().push(i);
+        }
+    }
+
+    let mut visited = vec![false; areas.len()];
+    let mut components = Vec::new();
+    for first in 0..areas.len() {
+        if visited[first] {
+            continue;
+        }
+        visited[first] = true;
+
+        let mut cells: Vec<Position> = Vec::new();
+        let mut cell_idxs: HashMap<Position, usize> = HashMap::new();
+        let mut area_idxs = Vec::new();
+        let mut stack = vec![first];
+        while let Some(a) = stack.pop() {
+            area_idxs.push(a);
+            let mut positions: Vec<Position> = areas[a].positions().iter().copied().collect();
+            positions.sort_unstable_by_key(|pos| (pos.y, pos.x));
+
+            for pos in positions {
+                if cell_idxs.contains_key(&pos) {
+                    continue;
+                }
+                cell_idxs.insert(pos, cells.len());
+                cells.push(pos);
+                for &next in &areas_at[&pos] {
+                    if !visited[next] {
+                        visited[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        let constraints = area_idxs
+            .into_iter()
+            .map(|a| {
+                let area = &areas[a];
+                let idxs = area.positions().iter().map(|pos| cell_idxs[pos]).collect();
+                (idxs, area.mine_count().min())
+            })
+            .collect();
+        components.push(Component { cells, constraints });
+    }
+
+    components
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate().filter(|(_, &x)| x != 0.0) {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+/// Calculates the probability of being a mine for every closed and flagged tile, using
+/// only the information visible to the player and the total number of mines. Flags are
+/// assumed to be correct and their tiles have probability of `1.0`.
+///
+/// Probabilities are exact. Closed tiles next to open numbers, the frontier, are split
+/// into independent components whose mine configurations are enumerated. Configurations
+/// are weighted by the number of ways to place the remaining mines on the other closed
+/// tiles, which are handled as a single group and share the same probability.
+///
+/// Returns [`None`] if no mine layout is consistent with the open tiles and flags, or if
+/// the board allows more than one mine per cell, as configurations are only enumerated
+/// with one mine per tile.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::position::Position;
+/// use mineraker::probability;
+///
+/// let gs = GenerationSettings {
+///     width: 9,
+///     height: 9,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let board = Board::new(&gs);
+///
+/// // Nothing is open, so every tile is equally likely to be a mine.
+/// let probabilities = probability::mine_probabilities(&board).unwrap();
+/// assert_eq!(probabilities.len(), 81);
+/// assert!((probabilities[&Position::new(3, 3)] - 10.0 / 81.0).abs() < 1e-9);
+/// ```
+pub fn mine_probabilities<T: Topology>(board: &Board<T>) -> Option<HashMap<Position, f64>> {
+    if board.mines_per_cell() > 1 {
+        return None;
+    }
+    let mut probabilities = HashMap::new();
+    let mut areas = Vec::new();
+    let mut closed = Vec::new();
+    for (pos, tile) in board.tiles_and_pos() {
+        match tile.state() {
+            State::Flag => {
+                probabilities.insert(pos, 1.0);
+            }
+            State::Closed => closed.push(pos),
+            State::Open => {
+                if let Value::Near(n) = tile.value() {
+                    let flags = board
+                        .neighbors_tile_and_pos(pos)
+                        .filter(|(_, tile)| tile.state() == State::Flag)
+                        .count();
+                    if flags > n as usize {
+                        return None;
+                    }
+                    let area = board.tile_neighbors_area(pos);
+                    if area.mine_count().min() > area.positions().len() {
+                        return None;
+                    }
+                    if !area.positions().is_empty() {
+                        areas.push(area);
+                    }
+                }
+            }
+        }
+    }
+    let remaining = board.mine_count().checked_sub(probabilities.len())?;
+
+    let components = components(&areas);
+    let configurations: Vec<Configurations> = components.iter().map(|c| c.enumerate()).collect();
+    let frontier: HashSet<Position> = components
+        .iter()
+        .flat_map(|c| c.cells.iter().copied())
+        .collect();
+    let interior: Vec<Position> = closed
+        .into_iter()
+        .filter(|pos| !frontier.contains(pos))
+        .collect();
+
+    // Logarithm of the number of ways to place `m` mines on the interior tiles.
+    let ln_factorials: Vec<f64> = std::iter::once(0.0)
+        .chain((1..=interior.len()).scan(0.0, |acc, n| {
+            *acc += (n as f64).ln();
+            Some(*acc)
+        }))
+        .collect();
+    let ln_ways = |frontier_mines: usize| -> Option<f64> {
+        let m = remaining.checked_sub(frontier_mines)?;
+        let n = interior.len();
+        (m <= n).then(|| ln_factorials[n] - ln_factorials[m] - ln_factorials[n - m])
+    };
+
+    let total = configurations
+        .iter()
+        .fold(vec![1.0], |acc, c| convolve(&acc, &c.counts));
+    // Normalize weights by the largest one to keep them in the range of `f64`.
+    let ln_max = (0..total.len())
+        .filter(|&k| total[k] != 0.0)
+        .filter_map(ln_ways)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let weight = |frontier_mines: usize| -> f64 {
+        ln_ways(frontier_mines).map_or(0.0, |ln| (ln - ln_max).exp())
+    };
+
+    let total_weight: f64 = total.iter().enumerate().map(|(k, n)| n * weight(k)).sum();
+    if total_weight == 0.0 || !total_weight.is_finite() {
+        return None;
+    }
+
+    for (i, (component, config)) in components.iter().zip(&configurations).enumerate() {
+        let others = configurations
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(vec![1.0], |acc, (_, c)| convolve(&acc, &c.counts));
+
+        for (cell, &pos) in component.cells.iter().enumerate() {
+            let hits: f64 = (0..config.hits.len())
+                .map(|k| {
+                    let hit = config.hits[k][cell];
+                    if hit == 0.0 {
+                        return 0.0;
+                    }
+                    hit * others
+                        .iter()
+                        .enumerate()
+                        .map(|(o, n)| n * weight(k + o))
+                        .sum::<f64>()
+                })
+                .sum();
+            probabilities.insert(pos, hits / total_weight);
+        }
+    }
+
+    if !interior.is_empty() {
+        let interior_mines: f64 = total
+            .iter()
+            .enumerate()
+            .map(|(k, n)| n * weight(k) * remaining.saturating_sub(k) as f64)
+            .sum();
+        let probability = interior_mines / total_weight / interior.len() as f64;
+        probabilities.extend(interior.into_iter().map(|pos| (pos, probability)));
+    }
+
+    Some(probabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, GenerationSettings, Seed, StartPolicy},
+        position::Position,
+        probability::mine_probabilities,
+        solver,
+        tile::{State, Value},
+    };
+
+    #[test]
+    fn probabilities_sum_to_mine_count() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                width: 16,
+                height: 16,
+                mine_count: 40,
+                start_pos: Position::new(8, 8),
+                start_policy: StartPolicy::SafeNeighborhood,
+                ..Default::default()
+            };
+            let mut board = Board::new(&gs);
+            board.open_from(gs.start_pos);
+
+            let probabilities = mine_probabilities(&board).unwrap();
+            let sum: f64 = probabilities.values().sum();
+
+            assert!((sum - 40.0).abs() < 1e-6, "{}", sum);
+            assert!(probabilities
+                .values()
+                .all(|&p| (-1e-9..=1.0 + 1e-9).contains(&p)));
+        }
+    }
+
+    #[test]
+    fn deduced_tiles_are_certain() {
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                width: 16,
+                height: 16,
+                mine_count: 40,
+                start_pos: Position::new(8, 8),
+                start_policy: StartPolicy::SafeNeighborhood,
+                ..Default::default()
+            };
+            let mut board = Board::new(&gs);
+            board.open_from(gs.start_pos);
+            let probabilities = mine_probabilities(&board).unwrap();
+
+            for mv in solver::deduce(&board) {
+                let p = probabilities[&mv.pos()];
+                let is_mine = board.get_tile(mv.pos()).unwrap().value() == Value::Mine;
+                assert!((p - if is_mine { 1.0 } else { 0.0 }).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_probabilities_on_small_board() {
+        // 3x1 board with the only mine in the middle. Opened left tile shows "1", so the
+        // middle tile is certainly the mine and the right tile is certainly safe.
+        let board = (0..)
+            .map(|seed| {
+                Board::new(&GenerationSettings {
+                    seed: Seed::new(seed),
+                    width: 3,
+                    height: 1,
+                    mine_count: 1,
+                    ..Default::default()
+                })
+            })
+            .find(|board| board.get_tile(Position::new(1, 0)).unwrap().value() == Value::Mine)
+            .unwrap();
+        let mut board = board;
+        board.open_from(Position::new(0, 0));
+
+        let probabilities = mine_probabilities(&board).unwrap();
+
+        assert!((probabilities[&Position::new(1, 0)] - 1.0).abs() < 1e-9);
+        assert!(probabilities[&Position::new(2, 0)].abs() < 1e-9);
+        assert_eq!(
+            board.get_tile(Position::new(0, 0)).unwrap().state(),
+            State::Open
+        );
+    }
+
+    #[test]
+    fn wrong_flags_are_inconsistent() {
+        let mut board = Board::new(&GenerationSettings {
+            width: 3,
+            height: 1,
+            mine_count: 1,
+            ..Default::default()
+        });
+        board.flag_from(Position::new(1, 0));
+        board.flag_from(Position::new(2, 0));
+
+        assert_eq!(mine_probabilities(&board), None);
+    }
+}
@@ -0,0 +1,984 @@
+use std::time::Duration;
+
+use crate::{
+    board::{Board, BoardError, GenerationMode, GenerationSettings, Seed, StartPolicy},
+    game::{Game, GameError, Move, MoveResult},
+    position::Position,
+    topology::{Grid, Mask, Shape, Topology},
+};
+
+/// Magic bytes at the start of a binary replay.
+const MAGIC: &[u8; 4] = b"MRRP";
+/// Header line of a text replay, followed by the version number.
+const TEXT_HEADER: &str = "mineraker-replay";
+/// Current version of both replay formats.
+pub const VERSION: u8 = 1;
+
+/// [`Event`] is a move made at the given time since the start of the game. Times have
+/// millisecond precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub time: Duration,
+    pub mv: Move,
+}
+
+/// [`Replay`] stores everything needed to reproduce a game: settings of the board, the
+/// mine layout the settings produced when recorded and all moves made.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub settings: GenerationSettings,
+    /// Cells of the board, see [`Grid::masked`]. Mask has the width and height of the
+    /// settings.
+    pub mask: Option<Mask>,
+    /// Indexes of the cells with mines, which are in row-major order when the board has no
+    /// mask, sorted in ascending order. Cells with several mines are listed once per mine.
+    pub mines: Vec<usize>,
+    pub events: Vec<Event>,
+}
+
+/// [`Recorder`] plays a game and records its moves into a [`Replay`].
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use mineraker::board::GenerationSettings;
+/// use mineraker::game::Move;
+/// use mineraker::position::Position;
+/// use mineraker::replay::{Player, Recorder, Replay};
+///
+/// let gs = GenerationSettings {
+///     width: 8,
+///     height: 8,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let mut recorder = Recorder::new(&gs).unwrap();
+/// recorder.play(Move::Open(Position::new(0, 0)), Duration::ZERO).unwrap();
+/// recorder.play(Move::Flag(Position::new(7, 7)), Duration::from_millis(1500)).unwrap();
+/// let recorded = recorder.game().board().to_string();
+/// let replay = recorder.finish();
+///
+/// let restored = Replay::from_bytes(&replay.to_bytes()).unwrap();
+/// let mut player = Player::new(&restored).unwrap();
+/// player.play_to_end().unwrap();
+/// assert_eq!(player.game().board().to_string(), recorded);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    game: Game,
+    replay: Replay,
+}
+
+impl Recorder {
+    /// Starts recording a new game with a board generated from the given settings.
+    pub fn new(settings: &GenerationSettings) -> Result<Self, BoardError> {
+        settings.validate()?;
+        Self::with_grid(settings.grid(), settings)
+    }
+
+    /// Starts recording a new game with a board generated on `grid` like
+    /// [`Board::try_with_topology`]. Width, height and shape of the recorded settings are
+    /// the ones of the grid.
+    pub fn with_grid(grid: Grid, settings: &GenerationSettings) -> Result<Self, BoardError> {
+        let settings = GenerationSettings {
+            width: grid.width,
+            height: grid.height,
+            shape: grid.shape,
+            ..*settings
+        };
+        let mask = grid.mask.clone();
+        let board = Board::try_with_topology(grid, &settings)?;
+        Ok(Self {
+            replay: Replay {
+                settings,
+                mask,
+                mines: mine_indexes(&board),
+                events: Vec::new(),
+            },
+            game: Game::new(board),
+        })
+    }
+
+    /// Makes the move at the given time and records it if it changed any tiles. Time is
+    /// truncated to whole milliseconds.
+    pub fn play(&mut self, mv: Move, time: Duration) -> Result<MoveResult, GameError> {
+        let result = self.game.play(mv)?;
+        if !result.changed.is_empty() {
+            self.replay.events.push(Event {
+                time: Duration::from_millis(time.as_millis() as u64),
+                mv,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Returns the recorded game.
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Stops recording and returns the replay.
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+fn mine_indexes(board: &Board) -> Vec<usize> {
+    board
+        .tiles_and_pos()
+        .enumerate()
+        .flat_map(|(idx, (_, tile))| std::iter::repeat_n(idx, tile.mines() as usize))
+        .collect()
+}
+
+/// [`Divergence`] describes the first difference between a replay and its playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// Board generated from the recorded settings has a different mine layout. `pos` is
+    /// the position of the first cell where the layouts differ, which may also differ in
+    /// the number of mines.
+    Board { pos: Position, recorded_mine: bool },
+    /// Board generated from the recorded settings is invalid.
+    Settings(BoardError),
+    /// Game refused the recorded move at index `event`.
+    Move { event: usize, error: GameError },
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::Board { pos, recorded_mine } => write!(
+                f,
+                "tile at ({}, {}) was {} when recorded",
+                pos.x,
+                pos.y,
+                if *recorded_mine { "a mine" } else { "safe" }
+            ),
+            Divergence::Settings(err) => write!(f, "recorded settings are invalid: {}", err),
+            Divergence::Move { event, error } => {
+                write!(f, "recorded move {} was refused: {}", event, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// [`Player`] reproduces a [`Replay`] step by step on a board generated from its settings.
+#[derive(Debug, Clone)]
+pub struct Player<'a> {
+    replay: &'a Replay,
+    game: Game,
+    next: usize,
+}
+
+impl<'a> Player<'a> {
+    /// Generates the board from the recorded settings.
+    ///
+    /// # Errors
+    /// If the settings are invalid or the generated mine layout differs from the recorded.
+    pub fn new(replay: &'a Replay) -> Result<Self, Divergence> {
+        let grid = replay.settings.grid_with_mask(replay.mask.clone());
+        let board =
+            Board::try_with_topology(grid, &replay.settings).map_err(Divergence::Settings)?;
+        let mines = mine_indexes(&board);
+        if mines != replay.mines {
+            let mines_at = |mines: &[usize], idx: usize| {
+                mines.partition_point(|&m| m <= idx) - mines.partition_point(|&m| m < idx)
+            };
+            let idx = (0..board.topology().cell_count())
+                .find(|&idx| mines_at(&mines, idx) != mines_at(&replay.mines, idx))
+                .unwrap_or_default();
+            return Err(Divergence::Board {
+                pos: board.topology().to_position(idx),
+                recorded_mine: mines_at(&replay.mines, idx) > 0,
+            });
+        }
+
+        Ok(Self {
+            replay,
+            game: Game::new(board),
+            next: 0,
+        })
+    }
+
+    /// Plays the next recorded event. Returns [`None`] when all events have been played.
+    pub fn step(&mut self) -> Option<Result<(Event, MoveResult), Divergence>> {
+        let event = *self.replay.events.get(self.next)?;
+        let index = self.next;
+        self.next += 1;
+
+        Some(
+            self.game
+                .play(event.mv)
+                .map(|result| (event, result))
+                .map_err(|error| Divergence::Move {
+                    event: index,
+                    error,
+                }),
+        )
+    }
+
+    /// Plays all remaining events, stopping at the first divergence.
+    pub fn play_to_end(&mut self) -> Result<(), Divergence> {
+        while let Some(step) = self.step() {
+            step?;
+        }
+        Ok(())
+    }
+
+    /// Returns the game being played back.
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+}
+
+/// [`ReplayError`] is returned when a replay can't be decoded from its binary or text format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Input doesn't start with the replay magic bytes or header line.
+    BadMagic,
+    /// Replay was written in a format version this library can't read.
+    UnsupportedVersion(u8),
+    /// Input ends before the replay is complete.
+    UnexpectedEnd,
+    /// Binary replay has bytes after the last event.
+    TrailingBytes,
+    /// Binary replay has an invalid value in the given field.
+    InvalidField(&'static str),
+    /// Text replay has an invalid line, where the given field was expected.
+    InvalidLine { line: usize, expected: &'static str },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::BadMagic => write!(f, "input is not a replay"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "replay version {} is not supported", version)
+            }
+            ReplayError::UnexpectedEnd => write!(f, "replay ends unexpectedly"),
+            ReplayError::TrailingBytes => write!(f, "replay has bytes after the last event"),
+            ReplayError::InvalidField(field) => write!(f, "replay has an invalid {}", field),
+            ReplayError::InvalidLine { line, expected } => {
+                write!(f, "line {}: expected {}", line, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+fn policy_name(policy: StartPolicy) -> &'static str {
+    match policy {
+        StartPolicy::Unprotected => "unprotected",
+        StartPolicy::SafeTile => "safe-tile",
+        StartPolicy::SafeNeighborhood => "safe-neighborhood",
+    }
+}
+
+fn shape_name(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Bounded => "bounded",
+        Shape::Torus => "torus",
+        Shape::Hex => "hex",
+    }
+}
+
+fn move_name(mv: Move) -> &'static str {
+    match mv {
+        Move::Open(_) => "open",
+        Move::Flag(_) => "flag",
+        Move::Chord(_) => "chord",
+    }
+}
+
+impl Replay {
+    /// Encodes the replay in the versioned binary format. Integers are stored as LEB128
+    /// varints, except the seed which is a little-endian `u64`. Mask is stored as one bit
+    /// per position in row-major order, starting from the lowest bit of each byte, and is
+    /// followed by the number of mines per cell.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::GenerationSettings;
+    /// use mineraker::replay::{Recorder, Replay};
+    ///
+    /// let gs = GenerationSettings {
+    ///     width: 8,
+    ///     height: 8,
+    ///     mine_count: 10,
+    ///     ..Default::default()
+    /// };
+    /// let replay = Recorder::new(&gs).unwrap().finish();
+    /// let bytes = replay.to_bytes();
+    /// assert_eq!(&bytes[..5], b"MRRP\x01");
+    /// assert_eq!(Replay::from_bytes(&bytes).unwrap().mines, replay.mines);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+
+        let gs = &self.settings;
+        out.extend_from_slice(&gs.seed.get().to_le_bytes());
+        for n in [
+            gs.width,
+            gs.height,
+            gs.mine_count,
+            gs.start_pos.x,
+            gs.start_pos.y,
+        ] {
+            write_varint(&mut out, n as u64);
+        }
+        out.push(match gs.start_policy {
+            StartPolicy::Unprotected => 0,
+            StartPolicy::SafeTile => 1,
+            StartPolicy::SafeNeighborhood => 2,
+        });
+        match gs.mode {
+            GenerationMode::Random => out.push(0),
+            GenerationMode::NoGuess { max_attempts } => {
+                out.push(1);
+                write_varint(&mut out, max_attempts as u64);
+            }
+        }
+        out.push(match gs.shape {
+            Shape::Bounded => 0,
+            Shape::Torus => 1,
+            Shape::Hex => 2,
+        });
+        match &self.mask {
+            None => out.push(0),
+            Some(mask) => {
+                out.push(1);
+                let mut bits = vec![0u8; (gs.width * gs.height).div_ceil(8)];
+                for i in 0..mask.cell_count() {
+                    let idx = mask.position(i).to_index(gs.width);
+                    bits[idx / 8] |= 1 << (idx % 8);
+                }
+                out.extend_from_slice(&bits);
+            }
+        }
+        out.push(gs.mines_per_cell);
+
+        write_varint(&mut out, self.mines.len() as u64);
+        for &idx in &self.mines {
+            write_varint(&mut out, idx as u64);
+        }
+
+        write_varint(&mut out, self.events.len() as u64);
+        for event in &self.events {
+            write_varint(&mut out, event.time.as_millis() as u64);
+            out.push(match event.mv {
+                Move::Open(_) => 0,
+                Move::Flag(_) => 1,
+                Move::Chord(_) => 2,
+            });
+            write_varint(&mut out, event.mv.pos().x as u64);
+            write_varint(&mut out, event.mv.pos().y as u64);
+        }
+        out
+    }
+
+    /// Decodes a replay from the binary format written by [`Replay::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        match reader.byte()? {
+            VERSION => {}
+            version => return Err(ReplayError::UnsupportedVersion(version)),
+        }
+
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let width = reader.usize("width")?;
+        let height = reader.usize("height")?;
+        let mine_count = reader.usize("mine count")?;
+        let start_pos = Position::new(reader.usize("start")?, reader.usize("start")?);
+        let start_policy = match reader.byte()? {
+            0 => StartPolicy::Unprotected,
+            1 => StartPolicy::SafeTile,
+            2 => StartPolicy::SafeNeighborhood,
+            _ => return Err(ReplayError::InvalidField("start policy")),
+        };
+        let mode = match reader.byte()? {
+            0 => GenerationMode::Random,
+            1 => GenerationMode::NoGuess {
+                max_attempts: reader.usize("max attempts")?,
+            },
+            _ => return Err(ReplayError::InvalidField("generation mode")),
+        };
+        let shape = match reader.byte()? {
+            0 => Shape::Bounded,
+            1 => Shape::Torus,
+            2 => Shape::Hex,
+            _ => return Err(ReplayError::InvalidField("shape")),
+        };
+        let mask = match reader.byte()? {
+            0 => None,
+            1 => {
+                let len = width
+                    .checked_mul(height)
+                    .ok_or(ReplayError::InvalidField("mask"))?;
+                let bits = reader.take(len.div_ceil(8))?;
+                Some(Mask::from_fn(width, height, |pos| {
+                    let idx = pos.to_index(width);
+                    bits[idx / 8] & (1 << (idx % 8)) != 0
+                }))
+            }
+            _ => return Err(ReplayError::InvalidField("mask")),
+        };
+        let mines_per_cell = reader.byte()?;
+
+        let mine_len = reader.usize("mine list")?;
+        let mines = (0..mine_len)
+            .map(|_| reader.usize("mine index"))
+            .collect::<Result<_, _>>()?;
+
+        let event_len = reader.usize("event list")?;
+        let events = (0..event_len)
+            .map(|_| {
+                let time = Duration::from_millis(reader.varint("time")?);
+                let kind = reader.byte()?;
+                let pos = Position::new(reader.usize("position")?, reader.usize("position")?);
+                let mv = match kind {
+                    0 => Move::Open(pos),
+                    1 => Move::Flag(pos),
+                    2 => Move::Chord(pos),
+                    _ => return Err(ReplayError::InvalidField("move")),
+                };
+                Ok(Event { time, mv })
+            })
+            .collect::<Result<_, _>>()?;
+
+        if !reader.bytes.is_empty() {
+            return Err(ReplayError::TrailingBytes);
+        }
+
+        Ok(Self {
+            settings: GenerationSettings {
+                seed: Seed::new(seed),
+                width,
+                height,
+                mine_count,
+                start_pos,
+                start_policy,
+                mode,
+                shape,
+                mines_per_cell,
+            },
+            mask,
+            mines,
+            events,
+        })
+    }
+
+    /// Parses a replay from the text format written by [`Display`] for [`Replay`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use mineraker::game::Move;
+    /// use mineraker::position::Position;
+    /// use mineraker::topology::Shape;
+    /// use mineraker::replay::Replay;
+    ///
+    /// let text = "\
+    /// mineraker-replay 1
+    /// seed 7
+    /// size 4 3
+    /// mines 2
+    /// start 0 0
+    /// policy safe-tile
+    /// mode random
+    /// shape torus
+    /// mask .### #### ###.
+    /// mines-per-cell 2
+    /// layout 4 4 10
+    /// events
+    /// 0 open 0 0
+    /// 1250 flag 1 1
+    /// ";
+    /// let replay = Replay::from_text(text).unwrap();
+    /// assert_eq!(replay.settings.width, 4);
+    /// assert_eq!(replay.settings.shape, Shape::Torus);
+    /// assert_eq!(replay.mask.as_ref().unwrap().cell_count(), 10);
+    /// assert_eq!(replay.settings.mines_per_cell, 2);
+    /// assert_eq!(replay.events[1].time, Duration::from_millis(1250));
+    /// assert_eq!(replay.events[1].mv, Move::Flag(Position::new(1, 1)));
+    /// assert_eq!(replay.to_string(), text);
+    /// ```
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split_whitespace().collect::<Vec<_>>()))
+            .filter(|(_, words)| !words.is_empty());
+
+        let (_, header) = lines.next().ok_or(ReplayError::UnexpectedEnd)?;
+        if header.first() != Some(&TEXT_HEADER) {
+            return Err(ReplayError::BadMagic);
+        }
+        match header.get(1).and_then(|v| v.parse::<u8>().ok()) {
+            Some(VERSION) => {}
+            Some(version) => return Err(ReplayError::UnsupportedVersion(version)),
+            None => return Err(ReplayError::BadMagic),
+        }
+
+        // Returns the words after `key` on the next line.
+        let mut field = |key: &'static str| {
+            let (line, words) = lines.next().ok_or(ReplayError::UnexpectedEnd)?;
+            match words.split_first() {
+                Some((&first, rest)) if first == key => {
+                    Ok((line, rest.iter().map(|w| w.to_string()).collect::<Vec<_>>()))
+                }
+                _ => Err(ReplayError::InvalidLine {
+                    line,
+                    expected: key,
+                }),
+            }
+        };
+        let numbers = |(line, words): (usize, Vec<String>), key, count: Option<usize>| {
+            let numbers = words
+                .iter()
+                .map(|w| w.parse::<u64>().ok())
+                .collect::<Option<Vec<_>>>()
+                .filter(|n| count.is_none_or(|count| n.len() == count));
+            numbers.ok_or(ReplayError::InvalidLine {
+                line,
+                expected: key,
+            })
+        };
+
+        let seed = numbers(field("seed")?, "seed", Some(1))?[0];
+        let size = numbers(field("size")?, "size", Some(2))?;
+        let mine_count = numbers(field("mines")?, "mines", Some(1))?[0];
+        let start = numbers(field("start")?, "start", Some(2))?;
+
+        let (line, words) = field("policy")?;
+        let start_policy = [
+            StartPolicy::Unprotected,
+            StartPolicy::SafeTile,
+            StartPolicy::SafeNeighborhood,
+        ]
+        .into_iter()
+        .find(|&policy| words == [policy_name(policy)])
+        .ok_or(ReplayError::InvalidLine {
+            line,
+            expected: "policy",
+        })?;
+
+        let (line, words) = field("mode")?;
+        let mode = match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["random"] => GenerationMode::Random,
+            ["no-guess", attempts] => GenerationMode::NoGuess {
+                max_attempts: attempts.parse().map_err(|_| ReplayError::InvalidLine {
+                    line,
+                    expected: "mode",
+                })?,
+            },
+            _ => {
+                return Err(ReplayError::InvalidLine {
+                    line,
+                    expected: "mode",
+                })
+            }
+        };
+
+        let (line, words) = field("shape")?;
+        let shape = [Shape::Bounded, Shape::Torus, Shape::Hex]
+            .into_iter()
+            .find(|&shape| words == [shape_name(shape)])
+            .ok_or(ReplayError::InvalidLine {
+                line,
+                expected: "shape",
+            })?;
+
+        let (line, words) = field("mask")?;
+        let invalid = ReplayError::InvalidLine {
+            line,
+            expected: "mask",
+        };
+        let mask = match &words[..] {
+            [none] if none == "none" => None,
+            rows => Some(rows.join("\n").parse::<Mask>().map_err(|_| invalid)?),
+        };
+
+        let (line, words) = field("mines-per-cell")?;
+        let mines_per_cell = match &words[..] {
+            [n] => n.parse().ok(),
+            _ => None,
+        }
+        .ok_or(ReplayError::InvalidLine {
+            line,
+            expected: "mines-per-cell",
+        })?;
+
+        let mines = numbers(field("layout")?, "layout", None)?;
+        let (line, words) = field("events")?;
+        if !words.is_empty() {
+            return Err(ReplayError::InvalidLine {
+                line,
+                expected: "events",
+            });
+        }
+
+        let events = lines
+            .map(|(line, words)| {
+                let invalid = ReplayError::InvalidLine {
+                    line,
+                    expected: "event",
+                };
+                let [time, kind, x, y] = words[..] else {
+                    return Err(invalid);
+                };
+                let number = |w: &str| w.parse::<usize>().map_err(|_| invalid);
+                let pos = Position::new(number(x)?, number(y)?);
+                let mv = match kind {
+                    "open" => Move::Open(pos),
+                    "flag" => Move::Flag(pos),
+                    "chord" => Move::Chord(pos),
+                    _ => return Err(invalid),
+                };
+                let time = Duration::from_millis(time.parse().map_err(|_| invalid)?);
+                Ok(Event { time, mv })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            settings: GenerationSettings {
+                seed: Seed::new(seed),
+                width: size[0] as usize,
+                height: size[1] as usize,
+                mine_count: mine_count as usize,
+                start_pos: Position::new(start[0] as usize, start[1] as usize),
+                start_policy,
+                mode,
+                shape,
+                mines_per_cell,
+            },
+            mask,
+            mines: mines.into_iter().map(|idx| idx as usize).collect(),
+            events,
+        })
+    }
+}
+
+/// Writes the replay in the readable text format: a header line, one line per setting,
+/// the mine layout, then one line per event with its time in milliseconds, the move and
+/// its position.
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gs = &self.settings;
+        writeln!(f, "{} {}", TEXT_HEADER, VERSION)?;
+        writeln!(f, "seed {}", gs.seed.get())?;
+        writeln!(f, "size {} {}", gs.width, gs.height)?;
+        writeln!(f, "mines {}", gs.mine_count)?;
+        writeln!(f, "start {} {}", gs.start_pos.x, gs.start_pos.y)?;
+        writeln!(f, "policy {}", policy_name(gs.start_policy))?;
+        match gs.mode {
+            GenerationMode::Random => writeln!(f, "mode random")?,
+            GenerationMode::NoGuess { max_attempts } => {
+                writeln!(f, "mode no-guess {}", max_attempts)?
+            }
+        }
+        writeln!(f, "shape {}", shape_name(gs.shape))?;
+        match &self.mask {
+            None => writeln!(f, "mask none")?,
+            Some(mask) => writeln!(f, "mask {}", mask.to_string().trim_end().replace('\n', " "))?,
+        }
+        writeln!(f, "mines-per-cell {}", gs.mines_per_cell)?;
+        write!(f, "layout")?;
+        for idx in &self.mines {
+            write!(f, " {}", idx)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "events")?;
+        for event in &self.events {
+            let pos = event.mv.pos();
+            writeln!(
+                f,
+                "{} {} {} {}",
+                event.time.as_millis(),
+                move_name(event.mv),
+                pos.x,
+                pos.y
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Reads values from the front of a binary replay.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReplayError> {
+        if self.bytes.len() < len {
+            return Err(ReplayError::UnexpectedEnd);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self, field: &'static str) -> Result<u64, ReplayError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 {
+                return Err(ReplayError::InvalidField(field));
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(ReplayError::InvalidField(field))
+    }
+
+    fn usize(&mut self, field: &'static str) -> Result<usize, ReplayError> {
+        usize::try_from(self.varint(field)?).map_err(|_| ReplayError::InvalidField(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        board::{GenerationMode, GenerationSettings, Seed, StartPolicy},
+        game::{GameError, Move, Status},
+        position::Position,
+        replay::{Divergence, Player, Recorder, Replay, ReplayError},
+        solver::deduce,
+        topology::{Grid, Mask, Shape},
+    };
+
+    fn recorded_game() -> Replay {
+        let gs = GenerationSettings {
+            seed: Seed::new(11),
+            width: 9,
+            height: 9,
+            mine_count: 10,
+            start_pos: Position::new(4, 4),
+            start_policy: StartPolicy::SafeNeighborhood,
+            mode: GenerationMode::NoGuess { max_attempts: 100 },
+            shape: Shape::Bounded,
+            mines_per_cell: 1,
+        };
+        let mut recorder = Recorder::new(&gs).unwrap();
+        let mut time = Duration::ZERO;
+        recorder.play(Move::Open(gs.start_pos), time).unwrap();
+        while !recorder.game().status().is_over() {
+            for mv in deduce(recorder.game().board()) {
+                if recorder.game().status().is_over() {
+                    break;
+                }
+                time += Duration::from_millis(321);
+                recorder.play(mv, time).unwrap();
+            }
+        }
+        assert_eq!(recorder.game().status(), Status::Won);
+        recorder.finish()
+    }
+
+    #[test]
+    fn playback_reproduces_game() {
+        let replay = recorded_game();
+        let mut player = Player::new(&replay).unwrap();
+        let mut steps = 0;
+        while let Some(step) = player.step() {
+            let (event, _) = step.unwrap();
+            assert_eq!(event, replay.events[steps]);
+            steps += 1;
+        }
+
+        assert_eq!(steps, replay.events.len());
+        assert_eq!(player.game().status(), Status::Won);
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        let replay = recorded_game();
+
+        let from_bytes = Replay::from_bytes(&replay.to_bytes()).unwrap();
+        assert_eq!(from_bytes.to_string(), replay.to_string());
+        assert_eq!(from_bytes.events, replay.events);
+
+        let from_text = Replay::from_text(&replay.to_string()).unwrap();
+        assert_eq!(from_text.to_bytes(), replay.to_bytes());
+
+        let torus = Replay {
+            settings: GenerationSettings {
+                shape: Shape::Torus,
+                ..replay.settings
+            },
+            ..replay.clone()
+        };
+        let from_bytes = Replay::from_bytes(&torus.to_bytes()).unwrap();
+        assert_eq!(from_bytes.settings.shape, Shape::Torus);
+        let from_text = Replay::from_text(&torus.to_string()).unwrap();
+        assert_eq!(from_text.settings.shape, Shape::Torus);
+
+        // Masks of 9x9 boards take 81 bits, which don't fill the last byte.
+        let mask = Mask::from_fn(9, 9, |pos| pos.x + pos.y < 12);
+        let masked = Replay {
+            mask: Some(mask.clone()),
+            ..replay
+        };
+        let from_bytes = Replay::from_bytes(&masked.to_bytes()).unwrap();
+        assert_eq!(from_bytes.mask.as_ref(), Some(&mask));
+        let from_text = Replay::from_text(&masked.to_string()).unwrap();
+        assert_eq!(from_text.mask.as_ref(), Some(&mask));
+        assert!(masked.to_string().contains("\nmask ######### ######### "));
+    }
+
+    #[test]
+    fn masked_games_play_back() {
+        let mask = Mask::from_fn(9, 9, |pos| pos.x + pos.y < 12);
+        let gs = GenerationSettings {
+            width: 20,
+            mode: GenerationMode::Random,
+            ..recorded_game().settings
+        };
+        let mut recorder =
+            Recorder::with_grid(Grid::masked(mask.clone(), Shape::Bounded), &gs).unwrap();
+        recorder
+            .play(Move::Open(gs.start_pos), Duration::ZERO)
+            .unwrap();
+        let recorded = recorder.game().board().to_string();
+        let replay = recorder.finish();
+        assert_eq!(replay.settings.width, 9);
+        assert_eq!(replay.mask.as_ref(), Some(&mask));
+
+        let restored = Replay::from_text(&replay.to_string()).unwrap();
+        let mut player = Player::new(&restored).unwrap();
+        player.play_to_end().unwrap();
+        assert_eq!(player.game().board().to_string(), recorded);
+    }
+
+    #[test]
+    fn stacked_mines_round_trip() {
+        let gs = GenerationSettings {
+            mine_count: 40,
+            mode: GenerationMode::Random,
+            mines_per_cell: 2,
+            ..recorded_game().settings
+        };
+        let mut recorder = Recorder::new(&gs).unwrap();
+        recorder
+            .play(Move::Open(gs.start_pos), Duration::ZERO)
+            .unwrap();
+        let replay = recorder.finish();
+        assert_eq!(replay.mines.len(), 40);
+        assert!(replay.mines.windows(2).any(|pair| pair[0] == pair[1]));
+
+        for restored in [
+            Replay::from_bytes(&replay.to_bytes()).unwrap(),
+            Replay::from_text(&replay.to_string()).unwrap(),
+        ] {
+            assert_eq!(restored.settings.mines_per_cell, 2);
+            assert_eq!(restored.mines, replay.mines);
+            assert!(Player::new(&restored).is_ok());
+        }
+
+        // Removing one of the two mines of a cell diverges at that cell.
+        let mut diverged = replay.clone();
+        let stacked = replay
+            .mines
+            .windows(2)
+            .position(|pair| pair[0] == pair[1])
+            .unwrap();
+        diverged.mines.remove(stacked);
+        assert!(matches!(
+            Player::new(&diverged),
+            Err(Divergence::Board { recorded_mine: true, pos })
+                if pos == Position::from_index(replay.mines[stacked], gs.width)
+        ));
+    }
+
+    #[test]
+    fn reports_board_divergence() {
+        let mut replay = recorded_game();
+        let first = replay.mines[0];
+        replay.mines[0] = first - 1;
+
+        let pos = Position::from_index(first - 1, replay.settings.width);
+        assert_eq!(
+            Player::new(&replay).unwrap_err(),
+            Divergence::Board {
+                pos,
+                recorded_mine: true
+            }
+        );
+    }
+
+    #[test]
+    fn reports_move_divergence() {
+        let mut replay = recorded_game();
+        replay.events.insert(
+            1,
+            super::Event {
+                time: Duration::ZERO,
+                mv: Move::Open(Position::new(20, 0)),
+            },
+        );
+
+        let mut player = Player::new(&replay).unwrap();
+        assert_eq!(
+            player.play_to_end(),
+            Err(Divergence::Move {
+                event: 1,
+                error: GameError::OutOfBounds(Position::new(20, 0))
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let bytes = recorded_game().to_bytes();
+
+        assert_eq!(
+            Replay::from_bytes(b"nope").unwrap_err(),
+            ReplayError::BadMagic
+        );
+        let mut future = bytes.clone();
+        future[4] = 9;
+        assert_eq!(
+            Replay::from_bytes(&future).unwrap_err(),
+            ReplayError::UnsupportedVersion(9)
+        );
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            ReplayError::UnexpectedEnd
+        );
+        assert_eq!(
+            Replay::from_text("mineraker-replay 1\nseed 1\nsize 4\n").unwrap_err(),
+            ReplayError::InvalidLine {
+                line: 3,
+                expected: "size"
+            }
+        );
+    }
+}
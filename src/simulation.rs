@@ -0,0 +1,508 @@
+use std::ops::Range;
+
+use rand::seq::SliceRandom;
+
+use crate::{
+    board::{Board, BoardError, GenerationSettings, Seed, StartPolicy},
+    game::{Game, Move, Status},
+    position::Position,
+    solver::Solver,
+    tile::State,
+    topology::{Grid, Mask},
+};
+
+/// Number of progress buckets in [`Report::losses_by_progress`].
+pub const PROGRESS_BUCKETS: usize = 10;
+
+/// Z-score of the 95% confidence level, to be used with [`Report::confidence_interval`].
+pub const Z_95: f64 = 1.959964;
+
+/// [`GuessStrategy`] chooses the tile to open when the solver can't deduce any moves.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GuessStrategy {
+    /// Opens the first closed tile in row-major order.
+    First,
+    /// Opens a random closed tile. Random choices are seeded by the board settings, so
+    /// the same board is always played the same way.
+    Random,
+    /// Opens the closed tile with the lowest mine probability, see
+    /// [`probability::mine_probabilities`](crate::probability::mine_probabilities). Ties
+    /// are broken in row-major order.
+    #[default]
+    LowestProbability,
+}
+
+impl GuessStrategy {
+    /// Returns the position of the closed tile to guess, or [`None`] if no tile is closed.
+    fn choose(self, game: &Game, rng: &mut rand_pcg::Pcg64Mcg) -> Option<Position> {
+        let board = game.board();
+        let mut closed = board
+            .tiles_and_pos()
+            .filter_map(|(pos, tile)| (tile.state() == State::Closed).then_some(pos));
+
+        match self {
+            GuessStrategy::First => closed.next(),
+            GuessStrategy::Random => closed.collect::<Vec<_>>().choose(rng).copied(),
+            GuessStrategy::LowestProbability => match board.mine_probabilities() {
+                // Candidates are in row-major order and `min_by` keeps the first of equal
+                // elements, so ties are broken deterministically.
+                Some(probabilities) => closed.min_by(|a, b| {
+                    probabilities[a]
+                        .partial_cmp(&probabilities[b])
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                }),
+                None => closed.next(),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for GuessStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(GuessStrategy::First),
+            "random" => Ok(GuessStrategy::Random),
+            "probability" => Ok(GuessStrategy::LowestProbability),
+            _ => Err(format!(
+                "unknown strategy {:?}, expected first, random or probability",
+                s
+            )),
+        }
+    }
+}
+
+/// [`Outcome`] of a single simulated game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// Status of the finished game, either won or lost.
+    pub status: Status,
+    /// Number of guesses made. Opening the start position counts as a guess only if it is
+    /// not protected by [`StartPolicy`].
+    pub guesses: usize,
+    /// Number of safe tiles opened before the game ended.
+    pub opened_safe: usize,
+    /// Total number of safe tiles on the board.
+    pub total_safe: usize,
+}
+
+impl Outcome {
+    /// Returns `true` if the game was won.
+    #[inline]
+    pub fn is_win(&self) -> bool {
+        self.status == Status::Won
+    }
+}
+
+/// Plays the board generated from the settings by opening the start position and then
+/// alternating deduction with guesses made by the strategy until the game is over.
+///
+/// # Errors
+/// If the board can't be generated from the settings.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{GenerationSettings, Seed};
+/// use mineraker::simulation::{self, GuessStrategy};
+///
+/// let gs = GenerationSettings {
+///     seed: Seed::new(3),
+///     width: 9,
+///     height: 9,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let outcome = simulation::play(&gs, GuessStrategy::LowestProbability).unwrap();
+///
+/// assert!(outcome.status.is_over());
+/// assert_eq!(outcome.is_win(), outcome.opened_safe == outcome.total_safe);
+/// ```
+pub fn play(settings: &GenerationSettings, strategy: GuessStrategy) -> Result<Outcome, BoardError> {
+    settings.validate()?;
+    play_on(settings.grid(), settings, strategy)
+}
+
+/// Plays the board generated on `grid` from the settings like [`play`], see
+/// [`Board::try_with_topology`].
+///
+/// [`Board::try_with_topology`]: crate::board::Board::try_with_topology
+///
+/// # Errors
+/// If the board can't be generated on the grid from the settings.
+pub fn play_on(
+    grid: Grid,
+    settings: &GenerationSettings,
+    strategy: GuessStrategy,
+) -> Result<Outcome, BoardError> {
+    let mut game = Game::new(Board::try_with_topology(grid, settings)?);
+    let total_safe = game.closed_safe_count();
+    // Guesses use a stream of their own, which doesn't repeat the generation stream.
+    let mut rng = rand_pcg::Pcg64Mcg::new(settings.to_state_seed().rotate_left(64));
+    let mut guesses = (settings.start_policy == StartPolicy::Unprotected) as usize;
+
+    let mut next = Some(settings.start_pos);
+    while let Some(pos) = next {
+        // Valid settings keep the start position on the board and guesses are closed tiles.
+        game.play(Move::Open(pos))
+            .expect("opened tile should be valid");
+
+        let mut solver = Solver::new(game);
+        solver.solve();
+        game = solver.into_game();
+        next = match game.status() {
+            Status::Playing => strategy.choose(&game, &mut rng),
+            _ => None,
+        };
+        guesses += next.is_some() as usize;
+    }
+
+    Ok(Outcome {
+        status: game.status(),
+        guesses,
+        opened_safe: total_safe - game.closed_safe_count(),
+        total_safe,
+    })
+}
+
+/// [`Report`] aggregates the outcomes of simulated games.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Number of games played.
+    pub games: u64,
+    /// Number of games won.
+    pub wins: u64,
+    /// Total number of guesses made in all games.
+    pub guesses: u64,
+    /// Number of lost games by the share of safe tiles opened before losing. Bucket `i`
+    /// holds losses with at least `i / PROGRESS_BUCKETS` of the safe tiles opened.
+    pub losses_by_progress: [u64; PROGRESS_BUCKETS],
+    /// Number of lost games by the guess which lost them. Index `0` is the first guess.
+    pub losses_by_guess: Vec<u64>,
+}
+
+impl Report {
+    /// Adds the outcome of a game to the report.
+    pub fn add(&mut self, outcome: &Outcome) {
+        self.games += 1;
+        self.guesses += outcome.guesses as u64;
+        if outcome.is_win() {
+            self.wins += 1;
+            return;
+        }
+
+        let bucket = outcome.opened_safe * PROGRESS_BUCKETS / outcome.total_safe.max(1);
+        self.losses_by_progress[bucket.min(PROGRESS_BUCKETS - 1)] += 1;
+        // Games are only lost by guesses, so the last guess lost the game.
+        let guess = outcome.guesses.saturating_sub(1);
+        if self.losses_by_guess.len() <= guess {
+            self.losses_by_guess.resize(guess + 1, 0);
+        }
+        self.losses_by_guess[guess] += 1;
+    }
+
+    /// Combines the games of another report into this one.
+    pub fn merge(&mut self, other: &Report) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.guesses += other.guesses;
+        for (a, b) in self
+            .losses_by_progress
+            .iter_mut()
+            .zip(other.losses_by_progress)
+        {
+            *a += b;
+        }
+        if self.losses_by_guess.len() < other.losses_by_guess.len() {
+            self.losses_by_guess.resize(other.losses_by_guess.len(), 0);
+        }
+        for (a, b) in self.losses_by_guess.iter_mut().zip(&other.losses_by_guess) {
+            *a += b;
+        }
+    }
+
+    /// Returns the number of games lost.
+    #[inline]
+    pub fn losses(&self) -> u64 {
+        self.games - self.wins
+    }
+
+    /// Returns the share of games won, or `0.0` if no games were played.
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.games as f64
+    }
+
+    /// Returns the average number of guesses per game, or `0.0` if no games were played.
+    pub fn average_guesses(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.guesses as f64 / self.games as f64
+    }
+
+    /// Returns the Wilson score interval of the win rate for the given z-score, such as
+    /// [`Z_95`]. Unlike the normal approximation, the interval stays within `0.0..=1.0`
+    /// and is meaningful for win rates close to either end.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::simulation::{Report, Z_95};
+    ///
+    /// let report = Report { games: 100, wins: 50, ..Default::default() };
+    /// let (low, high) = report.confidence_interval(Z_95);
+    /// assert!((low - 0.404).abs() < 1e-3);
+    /// assert!((high - 0.596).abs() < 1e-3);
+    ///
+    /// assert_eq!(Report::default().confidence_interval(Z_95), (0.0, 1.0));
+    /// ```
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        if self.games == 0 {
+            return (0.0, 1.0);
+        }
+        let n = self.games as f64;
+        let p = self.win_rate();
+        let z2 = z * z;
+
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let margin = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        ((center - margin).max(0.0), (center + margin).min(1.0))
+    }
+}
+
+/// [`Simulation`] plays boards of a range of seeds with the solver and a guessing strategy.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Settings of the boards. The seed is replaced by each seed of `seeds`.
+    pub settings: GenerationSettings,
+    /// Cells of the boards, see [`Grid::masked`]. Width and height of the mask replace the
+    /// ones of the settings.
+    pub mask: Option<Mask>,
+    pub seeds: Range<u64>,
+    pub strategy: GuessStrategy,
+}
+
+impl Simulation {
+    /// Plays all seeds in parallel on every available core. See [`Simulation::run_on`].
+    pub fn run(&self) -> Result<Report, BoardError> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.run_on(threads)
+    }
+
+    /// Plays all seeds split over the given number of threads and returns the combined
+    /// report, which doesn't depend on the number of threads.
+    ///
+    /// # Errors
+    /// If the settings are invalid, or a board of some seed can't be generated, in which
+    /// case the error of the lowest such seed is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::board::GenerationSettings;
+    /// use mineraker::simulation::{GuessStrategy, Simulation};
+    ///
+    /// let simulation = Simulation {
+    ///     settings: GenerationSettings {
+    ///         width: 9,
+    ///         height: 9,
+    ///         mine_count: 10,
+    ///         ..Default::default()
+    ///     },
+    ///     mask: None,
+    ///     seeds: 0..40,
+    ///     strategy: GuessStrategy::LowestProbability,
+    /// };
+    /// let report = simulation.run_on(4).unwrap();
+    ///
+    /// assert_eq!(report.games, 40);
+    /// assert_eq!(report, simulation.run_on(1).unwrap());
+    /// ```
+    pub fn run_on(&self, threads: usize) -> Result<Report, BoardError> {
+        // Sizes of the settings are checked before their cells are counted.
+        if self.mask.is_none() {
+            self.settings.validate()?;
+        }
+        let grid = self.settings.grid_with_mask(self.mask.clone());
+        self.settings.validate_in(&grid)?;
+        let threads = threads.clamp(
+            1,
+            1 + self.seeds.end.saturating_sub(self.seeds.start) as usize,
+        );
+
+        // Seeds are interleaved over the threads, as games of nearby seeds are no more
+        // alike than others and interleaving evens out the work.
+        let results: Vec<Result<Report, (u64, BoardError)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|offset| {
+                    let grid = &grid;
+                    scope.spawn(move || {
+                        let mut report = Report::default();
+                        for seed in self.seeds.clone().skip(offset).step_by(threads) {
+                            let settings = GenerationSettings {
+                                seed: Seed::new(seed),
+                                ..self.settings
+                            };
+                            let outcome = play_on(grid.clone(), &settings, self.strategy)
+                                .map_err(|err| (seed, err))?;
+                            report.add(&outcome);
+                        }
+                        Ok(report)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("simulation thread panicked"))
+                .collect()
+        });
+
+        let mut report = Report::default();
+        let mut first_error: Option<(u64, BoardError)> = None;
+        for result in results {
+            match result {
+                Ok(part) => report.merge(&part),
+                Err((seed, err)) => {
+                    if first_error.is_none_or(|(first, _)| seed < first) {
+                        first_error = Some((seed, err));
+                    }
+                }
+            }
+        }
+
+        match first_error {
+            Some((_, err)) => Err(err),
+            None => Ok(report),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{GenerationSettings, Seed, StartPolicy},
+        game::Status,
+        position::Position,
+        simulation::{play, play_on, GuessStrategy, Outcome, Report, Simulation},
+        topology::{Grid, Mask, Shape},
+    };
+
+    fn settings(seed: u64) -> GenerationSettings {
+        GenerationSettings {
+            seed: Seed::new(seed),
+            width: 16,
+            height: 16,
+            mine_count: 40,
+            start_pos: Position::new(8, 8),
+            start_policy: StartPolicy::SafeNeighborhood,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn games_are_played_to_the_end() {
+        for strategy in [
+            GuessStrategy::First,
+            GuessStrategy::Random,
+            GuessStrategy::LowestProbability,
+        ] {
+            for seed in 0..10 {
+                let outcome = play(&settings(seed), strategy).unwrap();
+
+                assert!(outcome.status.is_over());
+                assert_eq!(outcome.is_win(), outcome.opened_safe == outcome.total_safe);
+                assert_eq!(outcome, play(&settings(seed), strategy).unwrap());
+                if !outcome.is_win() {
+                    assert!(outcome.guesses > 0, "deduction alone should never lose");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn losses_are_bucketed() {
+        let mut report = Report::default();
+        let lost = |guesses, opened_safe| Outcome {
+            status: Status::Lost {
+                exploded_at: Position::default(),
+            },
+            guesses,
+            opened_safe,
+            total_safe: 100,
+        };
+        report.add(&lost(1, 0));
+        report.add(&lost(3, 55));
+        report.add(&lost(3, 99));
+        report.add(&Outcome {
+            status: Status::Won,
+            guesses: 2,
+            opened_safe: 100,
+            total_safe: 100,
+        });
+
+        assert_eq!(report.games, 4);
+        assert_eq!(report.losses(), 3);
+        assert_eq!(report.average_guesses(), 9.0 / 4.0);
+        assert_eq!(report.losses_by_progress, [1, 0, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(report.losses_by_guess, [1, 0, 2]);
+    }
+
+    #[test]
+    fn report_is_independent_of_threads() {
+        let simulation = Simulation {
+            settings: settings(0),
+            mask: None,
+            seeds: 100..150,
+            strategy: GuessStrategy::Random,
+        };
+        let report = simulation.run_on(1).unwrap();
+
+        assert_eq!(report.games, 50);
+        assert_eq!(report, simulation.run_on(3).unwrap());
+        assert_eq!(report, simulation.run_on(64).unwrap());
+    }
+
+    #[test]
+    fn invalid_settings_are_errors() {
+        let simulation = Simulation {
+            settings: GenerationSettings {
+                mine_count: 300,
+                ..settings(0)
+            },
+            mask: None,
+            seeds: 0..10,
+            strategy: GuessStrategy::First,
+        };
+
+        assert!(simulation.run().is_err());
+    }
+
+    #[test]
+    fn masked_boards_only_have_cells_inside_the_mask() {
+        // Ring of cells around a 6x6 hole in the middle of a 16x16 board.
+        let mask = Mask::from_fn(16, 16, |pos| {
+            !(5..11).contains(&pos.x) || !(5..11).contains(&pos.y)
+        });
+        let grid = Grid::masked(mask.clone(), Shape::Bounded);
+        let start = GenerationSettings {
+            start_pos: Position::new(0, 0),
+            ..settings(0)
+        };
+        let outcome = play_on(grid, &start, GuessStrategy::First).unwrap();
+        assert_eq!(outcome.total_safe, 220 - 40);
+
+        let simulation = Simulation {
+            settings: start,
+            mask: Some(mask),
+            seeds: 0..10,
+            strategy: GuessStrategy::First,
+        };
+        assert_eq!(simulation.run_on(2).unwrap().games, 10);
+        let in_hole = Simulation {
+            settings: settings(0),
+            ..simulation
+        };
+        assert!(in_hole.run().is_err());
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    area::{Area, AreaAction},
+    board::Board,
+    game::{Game, Move, Status},
+    position::Position,
+    tile::{State, Value},
+    topology::{Grid, Topology},
+};
+
+/// Identifies an [`Area`] by its sorted tile indexes and mine count range.
+type AreaKey = (Vec<usize>, usize, usize);
+
+fn area_key(area: &Area, width: usize) -> AreaKey {
+    let mut idxs: Vec<usize> = area
+        .positions()
+        .iter()
+        .map(|pos| pos.to_index(width))
+        .collect();
+    idxs.sort_unstable();
+
+    (idxs, area.mine_count().min(), area.mine_count().max())
+}
+
+/// Returns the closed neighbors of every open number tile as areas. Flags are taken as the
+/// number of mines in their tile.
+fn number_areas<T: Topology>(board: &Board<T>) -> Vec<Area> {
+    let mut known = HashSet::new();
+    board
+        .tiles_and_pos()
+        .filter(|(_, tile)| tile.state() == State::Open && tile.value() != Value::Mine)
+        .map(|(pos, _)| board.tile_neighbors_area(pos))
+        .filter(|area| !area.positions().is_empty())
+        .filter(|area| known.insert(area_key(area, board.width())))
+        .collect()
+}
+
+/// Returns the area of all closed tiles with the number of mines not yet flagged.
+fn remaining_area<T: Topology>(board: &Board<T>) -> Area {
+    let flags: usize = board
+        .tiles_and_pos()
+        .map(|(_, tile)| tile.flags() as usize)
+        .sum();
+    let closed: HashSet<Position> = board
+        .tiles_and_pos()
+        .filter_map(|(pos, tile)| (tile.state() == State::Closed).then_some(pos))
+        .collect();
+
+    Area::with_mines_per_position(
+        closed,
+        board.mine_count().saturating_sub(flags),
+        board.mines_per_cell() as usize,
+    )
+}
+
+/// Returns the moves of every area which has an action, sorted in row-major order.
+fn area_moves(areas: &[Area], width: usize, flags_per_mine: usize) -> Vec<Move> {
+    let mut moves: HashMap<usize, Move> = HashMap::new();
+    for area in areas {
+        if let Some(action) = area.next_action() {
+            for &pos in area.positions() {
+                let mv = match action {
+                    AreaAction::Open => Move::Open(pos),
+                    AreaAction::Flag => Move::Flag(pos),
+                };
+                moves.insert(pos.to_index(width), mv);
+            }
+        }
+    }
+
+    let mut moves: Vec<_> = moves.into_iter().collect();
+    moves.sort_unstable_by_key(|(idx, _)| *idx);
+    moves
+        .into_iter()
+        .flat_map(|(_, mv)| {
+            let count = match mv {
+                Move::Flag(_) => flags_per_mine,
+                _ => 1,
+            };
+            std::iter::repeat_n(mv, count)
+        })
+        .collect()
+}
+
+/// Deduces which closed tiles are certainly safe or certainly mines, using only the
+/// information visible to the player. Returned moves are sorted in row-major order.
+///
+/// Areas around number tiles are first checked on their own with [`Area::next_action`].
+/// If none of them has an action, differences between overlapping areas are calculated and
+/// checked, and the differences are in turn compared with the areas they overlap until a
+/// round derives no new areas.
+/// Subsets are handled by the differences as well, as the difference of a superset and
+/// its subset has an exact mine count.
+///
+/// Last, the area of all closed tiles, which has the mines not yet flagged, is checked
+/// together with its differences to each of the other areas and to sets of areas which
+/// don't overlap each other.
+///
+/// On boards with several mines per cell, tiles are flagged only when they are known to be
+/// full, and get one [`Move::Flag`] per mine so that their flags count all of their mines.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::game::Move;
+/// use mineraker::position::Position;
+/// use mineraker::solver;
+/// use mineraker::tile::Value;
+///
+/// let gs = GenerationSettings {
+///     width: 9,
+///     height: 9,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let mut board = Board::with_empty_at(&gs, Position::new(4, 4)).unwrap();
+/// board.open_from(Position::new(4, 4));
+///
+/// for mv in solver::deduce(&board) {
+///     let is_mine = board.get_tile(mv.pos()).unwrap().value() == Value::Mine;
+///     assert_eq!(is_mine, matches!(mv, Move::Flag(_)));
+/// }
+/// ```
+pub fn deduce<T: Topology>(board: &Board<T>) -> Vec<Move> {
+    let width = board.width();
+    let flags_per_mine = board.mines_per_cell() as usize;
+    let mut areas = number_areas(board);
+    let mut known: HashSet<AreaKey> = areas.iter().map(|area| area_key(area, width)).collect();
+    // Areas from this index onwards haven't been compared with others yet.
+    let mut unprocessed = 0;
+
+    // Index number areas by position to find overlapping areas quickly. Derived areas
+    // are only compared with the number areas, which they are differences of.
+    let mut areas_at: HashMap<Position, Vec<usize>> = HashMap::new();
+    for (i, area) in areas.iter().enumerate() {
+        for &pos in area.positions() {
+            areas_at.entry(pos).or_default().push(i);
+        }
+    }
+
+    loop {
+        let moves = area_moves(&areas[unprocessed..], width, flags_per_mine);
+        if !moves.is_empty() {
+            return moves;
+        }
+
+        let mut derived = Vec::new();
+        for i in unprocessed..areas.len() {
+            let overlapping: HashSet<usize> = areas[i]
+                .positions()
+                .iter()
+                .flat_map(|pos| areas_at[pos].iter().copied())
+                .filter(|&j| j != i)
+                .collect();
+
+            for j in overlapping {
+                for diff in [
+                    areas[i].difference(&areas[j]),
+                    areas[j].difference(&areas[i]),
+                ] {
+                    let size = diff.positions().len();
+                    let informative =
+                        diff.mine_count().min() > 0 || diff.mine_count().max() < diff.capacity();
+                    if size > 0 && informative && known.insert(area_key(&diff, width)) {
+                        derived.push(diff);
+                    }
+                }
+            }
+        }
+        if derived.is_empty() {
+            break;
+        }
+
+        unprocessed = areas.len();
+        areas.extend(derived);
+    }
+
+    // The area of all closed tiles overlaps every other area, so its differences would
+    // keep deriving new areas. Instead, it is compared with each of the final areas once,
+    // and the areas with the tightest mine counts are subtracted from it one by one while
+    // they don't overlap the ones already subtracted.
+    let remaining = remaining_area(board);
+    let mut remaining_areas: Vec<Area> = std::iter::once(remaining.clone())
+        .chain(areas.iter().map(|area| remaining.difference(area)))
+        .collect();
+    areas.sort_by_key(|area| {
+        let count = area.mine_count();
+        (count.max() - count.min(), Reverse(area.positions().len()))
+    });
+    let mut rest = remaining;
+    for area in &areas {
+        if area.positions().is_subset(rest.positions()) {
+            rest = rest.difference(area);
+            remaining_areas.push(rest.clone());
+        }
+    }
+    remaining_areas.retain(|area| !area.positions().is_empty());
+    area_moves(&remaining_areas, width, flags_per_mine)
+}
+
+/// Result of a single [`Solver::step`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Moves deduced and made during the step.
+    Progress(Vec<Move>),
+    /// All safe tiles are open.
+    Solved,
+    /// Nothing more can be deduced from the open tiles, or the game is lost.
+    Stuck,
+}
+
+/// [`Solver`] plays a [`Game`] by making only moves which can be deduced from the open
+/// tiles. Solver doesn't open the first tile, so it starts stuck on a fully closed board.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::game::{Game, Move};
+/// use mineraker::position::Position;
+/// use mineraker::solver::{Solver, Step};
+///
+/// let gs = GenerationSettings {
+///     width: 9,
+///     height: 9,
+///     mine_count: 10,
+///     ..Default::default()
+/// };
+/// let mut game = Game::new(Board::with_empty_at(&gs, Position::new(4, 4)).unwrap());
+/// game.play(Move::Open(Position::new(4, 4))).unwrap();
+///
+/// let mut solver = Solver::new(game);
+/// let outcome = solver.solve();
+/// assert!(outcome == Step::Solved || outcome == Step::Stuck);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Solver<T = Grid> {
+    game: Game<T>,
+}
+
+impl<T: Topology> Solver<T> {
+    /// Creates a new solver which continues the given game.
+    pub fn new(game: Game<T>) -> Self {
+        Self { game }
+    }
+
+    /// Deduces all currently deducible moves and makes them.
+    pub fn step(&mut self) -> Step {
+        match self.game.status() {
+            Status::Won => return Step::Solved,
+            Status::Lost { .. } => return Step::Stuck,
+            _ => {}
+        }
+
+        let moves = deduce(self.game.board());
+        if moves.is_empty() {
+            return Step::Stuck;
+        }
+        for &mv in &moves {
+            if self.game.status().is_over() {
+                break;
+            }
+            // Deduced moves target closed tiles on the board, so they are always valid.
+            self.game.play(mv).expect("deduced move should be valid");
+        }
+
+        Step::Progress(moves)
+    }
+
+    /// Makes steps until the game is solved or the solver is stuck, and returns the last
+    /// step, which is either [`Step::Solved`] or [`Step::Stuck`].
+    pub fn solve(&mut self) -> Step {
+        loop {
+            match self.step() {
+                Step::Progress(_) => continue,
+                step => return step,
+            }
+        }
+    }
+
+    /// Returns the game played by the solver.
+    #[inline]
+    pub fn game(&self) -> &Game<T> {
+        &self.game
+    }
+
+    /// Consumes the solver and returns the game played by it.
+    #[inline]
+    pub fn into_game(self) -> Game<T> {
+        self.game
+    }
+}
+
+/// Returns `true` if all safe tiles of the board can be opened by deduction alone, when
+/// starting by opening the tile at `start`. Board is expected to have all tiles closed.
+pub(crate) fn is_solvable<T: Topology>(board: &Board<T>, start: Position) -> bool {
+    match board.get_tile(start) {
+        Some(tile) if tile.value() != Value::Mine => {}
+        _ => return false,
+    }
+
+    let mut game = Game::new(board.clone());
+    if game.play(Move::Open(start)).is_err() {
+        return false;
+    }
+
+    Solver::new(game).solve() == Step::Solved
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, GenerationSettings, Seed, StartPolicy},
+        game::{Game, Move, Status},
+        position::Position,
+        solver::{deduce, is_solvable, Solver, Step},
+        tile::{State, Value},
+    };
+
+    #[test]
+    fn deduction_never_opens_mines() {
+        for seed in 0..20 {
+            let mut board = Board::new(&GenerationSettings {
+                seed: Seed::new(seed),
+                width: 16,
+                height: 16,
+                mine_count: 40,
+                start_pos: Position::new(8, 8),
+                start_policy: StartPolicy::SafeNeighborhood,
+                ..Default::default()
+            });
+            board.open_from(Position::new(8, 8));
+
+            for mv in deduce(&board) {
+                let tile = board.get_tile(mv.pos()).unwrap();
+                assert_eq!(tile.state(), State::Closed);
+                assert_eq!(tile.value() == Value::Mine, matches!(mv, Move::Flag(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn mine_start_is_not_solvable() {
+        let board = Board::new(&GenerationSettings {
+            width: 2,
+            height: 1,
+            mine_count: 1,
+            start_policy: StartPolicy::Unprotected,
+            ..Default::default()
+        });
+        let mine = if board.get_tile(Position::new(0, 0)).unwrap().value() == Value::Mine {
+            Position::new(0, 0)
+        } else {
+            Position::new(1, 0)
+        };
+
+        assert!(!is_solvable(&board, mine));
+        // Remaining tile is a mine by the total mine count.
+        assert!(is_solvable(&board, Position::new(1 - mine.x, 0)));
+    }
+
+    #[test]
+    fn differences_solve_bottom_row() {
+        // Top row is open and bottom row holds both mines. Every mine layout can be
+        // deduced by combining a number with the global mine count.
+        let boards = (0..)
+            .map(|seed| {
+                Board::new(&GenerationSettings {
+                    seed: Seed::new(seed),
+                    width: 3,
+                    height: 2,
+                    mine_count: 2,
+                    ..Default::default()
+                })
+            })
+            .filter(|board| {
+                (0..3).all(|x| board.get_tile(Position::new(x, 0)).unwrap().value() != Value::Mine)
+            })
+            .take(5);
+
+        for mut board in boards {
+            (0..3).for_each(|x| {
+                board.open_from(Position::new(x, 0));
+            });
+            let moves = deduce(&board);
+
+            assert!(!moves.is_empty());
+            for mv in moves {
+                let is_mine = board.get_tile(mv.pos()).unwrap().value() == Value::Mine;
+                assert_eq!(is_mine, matches!(mv, Move::Flag(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn solver_reports_stuck_on_closed_board() {
+        let board = Board::new(&GenerationSettings {
+            width: 9,
+            height: 9,
+            mine_count: 10,
+            ..Default::default()
+        });
+        let mut solver = Solver::new(Game::new(board));
+
+        assert_eq!(solver.step(), Step::Stuck);
+        assert_eq!(solver.game().status(), Status::NotStarted);
+    }
+
+    #[test]
+    fn solver_never_loses() {
+        for seed in 0..20 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                width: 16,
+                height: 16,
+                mine_count: 40,
+                start_pos: Position::new(8, 8),
+                start_policy: StartPolicy::SafeNeighborhood,
+                ..Default::default()
+            };
+            let mut game = Game::from_settings(&gs).unwrap();
+            game.play(Move::Open(gs.start_pos)).unwrap();
+
+            let mut solver = Solver::new(game);
+            match solver.solve() {
+                Step::Solved => assert_eq!(solver.game().status(), Status::Won),
+                Step::Stuck => assert_eq!(solver.game().status(), Status::Playing),
+                Step::Progress(_) => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn solver_fills_stacked_mines() {
+        let mut solved = 0;
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                width: 9,
+                height: 9,
+                mine_count: 10,
+                mines_per_cell: 3,
+                start_pos: Position::new(4, 4),
+                start_policy: StartPolicy::SafeNeighborhood,
+                ..Default::default()
+            };
+            let mut game = Game::from_settings(&gs).unwrap();
+            game.play(Move::Open(gs.start_pos)).unwrap();
+
+            let mut solver = Solver::new(game);
+            if solver.solve() == Step::Solved {
+                solved += 1;
+            }
+            assert!(!matches!(solver.game().status(), Status::Lost { .. }));
+            // Tiles are only flagged when full, with a flag for every mine.
+            for (_, tile) in solver.game().board().tiles_and_pos() {
+                if tile.state() == State::Flag {
+                    assert_eq!(tile.flags(), 3);
+                    assert_eq!(tile.mines(), 3);
+                }
+            }
+        }
+        assert!(solved > 0);
+    }
+}
@@ -1,20 +1,121 @@
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct Tile {
-    value: Value,
-    state: State,
-}
+/// [`Tile`] of a board, packed into a single byte. Lower five bits hold the value, `0` to
+/// `28` for numbers and `29` to `31` for cells with three to one mines, and the upper three
+/// bits hold the state, where flags carry a counter of one to three. Numbers go up to 26
+/// on three-dimensional boards. Value and state are decoded on access, which keeps boards
+/// at one byte per tile.
+///
+/// # Examples
+/// ```
+/// use mineraker::tile::{State, Tile, Value};
+///
+/// assert_eq!(std::mem::size_of::<Tile>(), 1);
+///
+/// let mut tile = Tile::with_value(Value::Near(2));
+/// tile.toggle_flag();
+/// assert_eq!((tile.value(), tile.state()), (Value::Near(2), State::Flag));
+/// ```
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "TileRepr", into = "TileRepr")
+)]
+#[repr(transparent)]
+pub struct Tile(u8);
 
 impl Tile {
     /// Constant [`Tile`] which empty and closed.
-    pub const EMPTY_CLOSED: Self = Self {
-        value: Value::Near(0),
-        state: State::Closed,
-    };
+    pub const EMPTY_CLOSED: Self = Self(0);
+
+    /// Largest number of mines a single tile can hold.
+    pub const MAX_MINES: u8 = 3;
+
+    /// Largest number a tile can show, as the values above it are mines.
+    pub const MAX_NUMBER: u8 = Self::MINE - Self::MAX_MINES;
+
+    const VALUE_MASK: u8 = 0x1f;
+    const MINE: u8 = 31;
+    const STATE_SHIFT: u8 = 5;
+    const FLAG: u8 = 2;
 
-    /// Constructs a new [`Tile`] with the given value and state.
+    /// Constructs a new [`Tile`] with the given value and state. Mines hold a single mine
+    /// and flags have a single flag.
+    ///
+    /// # Panics
+    /// If the value is a number above [`Tile::MAX_NUMBER`].
     #[allow(dead_code)]
     pub fn new(value: Value, state: State) -> Self {
-        Self { value, state }
+        let value = match value {
+            Value::Near(n) => {
+                assert!(
+                    n <= Self::MAX_NUMBER,
+                    "tile value {} doesn't fit in a tile",
+                    n
+                );
+                n
+            }
+            Value::Mine => Self::MINE,
+        };
+        Self(Self::state_bits(state) | value)
+    }
+
+    /// Constructs a new closed [`Value::Mine`] tile which holds the given number of mines,
+    /// from one to [`Tile::MAX_MINES`].
+    ///
+    /// # Panics
+    /// If the number of mines is zero or above [`Tile::MAX_MINES`].
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::tile::{Tile, Value};
+    ///
+    /// let tile = Tile::with_mines(3);
+    /// assert_eq!((tile.value(), tile.mines()), (Value::Mine, 3));
+    /// assert_eq!(Tile::with_mines(1), Tile::with_value(Value::Mine));
+    /// ```
+    #[inline]
+    pub fn with_mines(mines: u8) -> Self {
+        assert!(
+            (1..=Self::MAX_MINES).contains(&mines),
+            "{} mines don't fit in a tile",
+            mines
+        );
+        Self(Self::MINE + 1 - mines)
+    }
+
+    #[inline]
+    fn state_bits(state: State) -> u8 {
+        let bits = match state {
+            State::Closed => 0,
+            State::Open => 1,
+            State::Flag => Self::FLAG,
+        };
+        bits << Self::STATE_SHIFT
+    }
+
+    #[inline]
+    fn set_state(&mut self, state: State) {
+        self.0 = Self::state_bits(state) | (self.0 & Self::VALUE_MASK);
+    }
+
+    #[inline]
+    fn set_flags(&mut self, flags: u8) {
+        self.0 = (Self::FLAG + flags - 1) << Self::STATE_SHIFT | (self.0 & Self::VALUE_MASK);
+    }
+
+    /// Returns this tile with the given state, keeping its value and number of mines.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::tile::{State, Tile};
+    ///
+    /// let open = Tile::with_mines(2).with_state(State::Open);
+    /// assert_eq!((open.mines(), open.state()), (2, State::Open));
+    /// ```
+    #[inline]
+    pub fn with_state(mut self, state: State) -> Self {
+        self.set_state(state);
+        self
     }
 
     /// Constructs a new [`Tile`] with given value and [`Default`] state.
@@ -28,10 +129,7 @@ impl Tile {
     /// ```
     #[inline]
     pub fn with_value(value: Value) -> Self {
-        Self {
-            value,
-            ..Default::default()
-        }
+        Self::new(value, State::default())
     }
 
     /// Increments [`Tile`]s value if it is not a mine. Useful in board generation.
@@ -51,16 +149,16 @@ impl Tile {
     /// ```
     #[inline]
     pub fn increment_value(&mut self) {
-        if let Value::Near(value) = &mut self.value {
-            *value += 1;
+        if let Value::Near(value) = self.value() {
+            *self = Self::new(Value::Near(value + 1), self.state());
         }
     }
 
     /// Opens this tile, but only if it is currently closed.
     #[inline]
     pub fn open(&mut self) {
-        if self.state == State::Closed {
-            self.state = State::Open;
+        if self.state() == State::Closed {
+            self.set_state(State::Open);
         }
     }
 
@@ -68,8 +166,39 @@ impl Tile {
     #[allow(dead_code)]
     #[inline]
     pub fn flag(&mut self) {
-        if self.state == State::Closed {
-            self.state = State::Flag;
+        if self.state() == State::Closed {
+            self.set_state(State::Flag);
+        }
+    }
+
+    /// Adds a flag to this tile, allowing at most `max_flags` flags. Closed tiles get one
+    /// flag, flags are counted up to `max_flags` and tiles with `max_flags` flags are set
+    /// closed. Open tiles won't change state. With one flag at most this is the same as
+    /// [`Tile::toggle_flag`].
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::tile::{State, Tile};
+    ///
+    /// let mut tile = Tile::with_mines(2);
+    /// tile.cycle_flag(3);
+    /// tile.cycle_flag(3);
+    /// assert_eq!((tile.state(), tile.flags()), (State::Flag, 2));
+    ///
+    /// tile.cycle_flag(3);
+    /// assert_eq!(tile.flags(), 3);
+    /// tile.cycle_flag(3);
+    /// assert_eq!((tile.state(), tile.flags()), (State::Closed, 0));
+    /// ```
+    #[inline]
+    pub fn cycle_flag(&mut self, max_flags: u8) {
+        match self.state() {
+            State::Closed => self.set_state(State::Flag),
+            State::Open => {}
+            State::Flag if self.flags() < max_flags.min(Self::MAX_MINES) => {
+                self.set_flags(self.flags() + 1)
+            }
+            State::Flag => self.set_state(State::Closed),
         }
     }
 
@@ -95,24 +224,80 @@ impl Tile {
     #[allow(dead_code)]
     #[inline]
     pub fn toggle_flag(&mut self) {
-        self.state = match self.state {
-            State::Closed => State::Flag,
-            State::Open => State::Open,
-            State::Flag => State::Closed,
-        };
+        match self.state() {
+            State::Closed => self.set_state(State::Flag),
+            State::Open => {}
+            State::Flag => self.set_state(State::Closed),
+        }
+    }
+
+    /// Encodes the tile into a single byte, which is the packed representation of the tile.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::tile::{State, Tile, Value};
+    ///
+    /// let tile = Tile::new(Value::Near(3), State::Flag);
+    /// assert_eq!(tile.to_byte(), 0x43);
+    /// assert_eq!(Tile::from_byte(tile.to_byte()), Some(tile));
+    /// ```
+    #[inline]
+    pub fn to_byte(self) -> u8 {
+        self.0
+    }
+
+    /// Decodes a tile from a byte created with [`Tile::to_byte`]. Returns [`None`] if the
+    /// byte is not a valid tile.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        // Every value fits in the value bits, so only the state can be invalid.
+        (byte >> Self::STATE_SHIFT < Self::FLAG + Self::MAX_MINES).then_some(Self(byte))
     }
 
     /// Returns the value of tile.
     #[inline]
     pub fn value(&self) -> Value {
-        self.value
+        match self.0 & Self::VALUE_MASK {
+            n if n > Self::MAX_NUMBER => Value::Mine,
+            n => Value::Near(n),
+        }
+    }
+
+    /// Returns the number of mines the tile holds, which is zero for numbers.
+    #[inline]
+    pub fn mines(&self) -> u8 {
+        match self.value() {
+            Value::Near(_) => 0,
+            Value::Mine => Self::MINE + 1 - (self.0 & Self::VALUE_MASK),
+        }
+    }
+
+    /// Returns the number of flags on the tile, which is zero unless the tile is flagged.
+    #[inline]
+    pub fn flags(&self) -> u8 {
+        match self.state() {
+            State::Flag => (self.0 >> Self::STATE_SHIFT) - Self::FLAG + 1,
+            _ => 0,
+        }
     }
 
     /// Returns the state of tile.
     #[allow(dead_code)]
     #[inline]
     pub fn state(&self) -> State {
-        self.state
+        match self.0 >> Self::STATE_SHIFT {
+            0 => State::Closed,
+            1 => State::Open,
+            _ => State::Flag,
+        }
+    }
+}
+
+impl std::fmt::Debug for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tile")
+            .field("value", &self.value())
+            .field("state", &self.state())
+            .finish()
     }
 }
 
@@ -121,18 +306,88 @@ impl std::fmt::Display for Tile {
         write!(
             f,
             "{}",
-            match self.state {
+            match self.state() {
                 State::Closed => "#".to_string(),
-                State::Open => self.value.to_string(),
+                State::Open => self.value().to_string(),
                 State::Flag => "?".to_string(),
             }
         )
     }
 }
 
-/// Value of a [`Tile`]. Value is either mine or number from 0 to 8, which represents the
-/// number of mines around the tile.
+/// Serialized form of a [`Tile`], which keeps the value and state as separate fields. Number
+/// of mines and flags is only stored when it is more than one. Fields which don't fit in a
+/// tile are rejected when deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Tile")]
+struct TileRepr {
+    value: Value,
+    state: State,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mines: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flags: Option<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TileRepr> for Tile {
+    type Error = String;
+
+    fn try_from(repr: TileRepr) -> Result<Self, Self::Error> {
+        let counts = 1..=Tile::MAX_MINES;
+        let mut tile = match (repr.value, repr.mines) {
+            (Value::Near(n), _) if n > Tile::MAX_NUMBER => {
+                return Err(format!("number {} doesn't fit in a tile", n))
+            }
+            (Value::Near(_), Some(_)) => return Err("only mines can hold mines".to_string()),
+            (Value::Mine, Some(mines)) if !counts.contains(&mines) => {
+                return Err(format!("{} mines don't fit in a tile", mines))
+            }
+            (Value::Mine, Some(mines)) => Tile::with_mines(mines),
+            (value, None) => Tile::with_value(value),
+        };
+        match (repr.state, repr.flags) {
+            (State::Flag, Some(flags)) if !counts.contains(&flags) => {
+                return Err(format!("{} flags don't fit in a tile", flags))
+            }
+            (State::Flag, flags) => tile.set_flags(flags.unwrap_or(1)),
+            (_, Some(_)) => return Err("only flagged tiles can have flags".to_string()),
+            (state, None) => tile.set_state(state),
+        }
+        Ok(tile)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Tile> for TileRepr {
+    fn from(tile: Tile) -> Self {
+        TileRepr {
+            value: tile.value(),
+            state: tile.state(),
+            mines: (tile.mines() > 1).then_some(tile.mines()),
+            flags: (tile.flags() > 1).then_some(tile.flags()),
+        }
+    }
+}
+
+/// Value of a [`Tile`]. Value is either mine or number, which represents the number of mines
+/// around the tile. Numbers go from 0 to 8 on flat boards and up to 26 on three-dimensional
+/// boards. On boards which allow several mines per cell, a mine tile may hold up to
+/// [`Tile::MAX_MINES`] mines, see [`Tile::mines`], and numbers count every mine up to
+/// [`Tile::MAX_NUMBER`]. Numbers above 9 are displayed as the letters `a` to `s`, which
+/// keeps every tile one character wide.
+///
+/// # Examples
+/// ```
+/// use mineraker::tile::Value;
+///
+/// assert_eq!(Value::Near(0).to_string(), "_");
+/// assert_eq!(Value::Near(8).to_string(), "8");
+/// assert_eq!(Value::Near(26).to_string(), "q");
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Near(u8),
     Mine,
@@ -145,7 +400,8 @@ impl std::fmt::Display for Value {
             "{}",
             match self {
                 Value::Near(0) => "_".to_string(),
-                Value::Near(val) => val.to_string(),
+                Value::Near(val) =>
+                    char::from_digit(*val as u32, 36).map_or_else(|| val.to_string(), String::from),
                 Value::Mine => "*".to_string(),
             }
         )
@@ -159,15 +415,56 @@ impl Default for Value {
 }
 
 /// State of [`Tile`] which is one of the following states: closed, open or flag.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
+    #[default]
     Closed,
     Open,
     Flag,
 }
 
-impl Default for State {
-    fn default() -> Self {
-        State::Closed
+#[cfg(test)]
+mod tests {
+    use crate::tile::{Tile, Value};
+
+    #[test]
+    #[should_panic]
+    fn numbers_above_max_panic() {
+        Tile::with_value(Value::Near(Tile::MAX_NUMBER + 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_mine_tiles_panic() {
+        Tile::with_mines(0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_fields_which_dont_fit() {
+        use crate::tile::State;
+
+        let tile = |json: &str| serde_json::from_str::<Tile>(json);
+
+        let stacked = Tile::with_mines(3).with_state(State::Open);
+        let json = serde_json::to_string(&stacked).unwrap();
+        assert_eq!(tile(&json).unwrap(), stacked);
+        assert_eq!(
+            tile(r#"{"value":{"Near":28},"state":"Open"}"#).unwrap(),
+            Tile::new(Value::Near(28), State::Open)
+        );
+
+        for json in [
+            r#"{"value":{"Near":29},"state":"Closed"}"#,
+            r#"{"value":{"Near":200},"state":"Open"}"#,
+            r#"{"value":{"Near":1},"state":"Closed","mines":2}"#,
+            r#"{"value":"Mine","state":"Closed","mines":0}"#,
+            r#"{"value":"Mine","state":"Closed","mines":4}"#,
+            r#"{"value":"Mine","state":"Flag","flags":4}"#,
+            r#"{"value":"Mine","state":"Open","flags":2}"#,
+        ] {
+            assert!(tile(json).is_err(), "{} should be rejected", json);
+        }
     }
 }
@@ -0,0 +1,853 @@
+use crate::position::{Point, Position};
+
+/// [`Topology`] describes the cells of a board and how they are connected. Every cell has
+/// an index, which is its place in the tiles of the board, and a [`Position`] inside the
+/// rectangle of [`Topology::width`] and [`Topology::height`]. Positions in the rectangle
+/// don't need to be cells, which allows boards with holes and irregular outlines.
+///
+/// Boards, their generation, cascades and the solver work through this trait, so custom
+/// shapes and neighborhoods plug into all of them. Topologies are cloned along with their
+/// boards. [`Grid`] is the default topology.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::position::Position;
+/// use mineraker::topology::Topology;
+///
+/// /// Single row where tiles are connected to the tiles two steps away.
+/// #[derive(Debug, Clone)]
+/// struct Skips(usize);
+///
+/// impl Topology for Skips {
+///     fn width(&self) -> usize {
+///         self.0
+///     }
+///     fn height(&self) -> usize {
+///         1
+///     }
+///     fn cell_count(&self) -> usize {
+///         self.0
+///     }
+///     fn to_index(&self, pos: Position) -> Option<usize> {
+///         (pos.x < self.0 && pos.y == 0).then_some(pos.x)
+///     }
+///     fn to_position(&self, index: usize) -> Position {
+///         Position::new(index, 0)
+///     }
+///     fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> {
+///         [pos.x.wrapping_sub(2), pos.x + 2]
+///             .into_iter()
+///             .filter(|&x| x < self.0)
+///             .map(|x| Position::new(x, 0))
+///     }
+/// }
+///
+/// let gs = GenerationSettings { mine_count: 0, ..Default::default() };
+/// let mut board = Board::try_with_topology(Skips(5), &gs).unwrap();
+/// let opened = board.open_from(Position::new(0, 0));
+/// assert_eq!(opened, [Position::new(0, 0), Position::new(2, 0), Position::new(4, 0)]);
+/// ```
+pub trait Topology: Clone {
+    /// Returns the width of the rectangle containing all cells.
+    fn width(&self) -> usize;
+
+    /// Returns the height of the rectangle containing all cells.
+    fn height(&self) -> usize;
+
+    /// Returns the number of cells.
+    fn cell_count(&self) -> usize;
+
+    /// Returns the index of the cell at `pos`, or [`None`] if there is no cell at `pos`.
+    fn to_index(&self, pos: Position) -> Option<usize>;
+
+    /// Returns the position of the cell with the given index. Index must be less than
+    /// [`Topology::cell_count`].
+    fn to_position(&self, index: usize) -> Position;
+
+    /// Returns iterator over the positions of the cells next to the cell at `pos`. A cell
+    /// is never its own neighbor, is returned at most once and neighbors are mutual.
+    fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position>;
+
+    /// Returns the largest number of neighbors of any cell, or an upper bound of it. By
+    /// default, the neighbors of every cell are counted.
+    fn max_neighbors(&self) -> usize {
+        (0..self.cell_count())
+            .map(|i| self.neighbors(self.to_position(i)).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if there is a cell at `pos`.
+    #[inline]
+    fn contains(&self, pos: Position) -> bool {
+        self.to_index(pos).is_some()
+    }
+
+    /// Returns `true` if the cells are the whole rectangle in row-major order, connected
+    /// like [`Shape::Bounded`]. Such topologies can use bit-parallel fast paths.
+    #[inline]
+    fn is_bounded_rectangle(&self) -> bool {
+        false
+    }
+}
+
+/// [`Grid`] is a rectangle of tiles in row-major order with the neighbors of its [`Shape`].
+/// Grid with a [`Mask`] only has cells inside the mask, and the mask must be as wide and
+/// high as the grid.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub shape: Shape,
+    pub mask: Option<Mask>,
+}
+
+impl Grid {
+    /// Creates a grid of the given width, height and shape.
+    #[inline]
+    pub fn new(width: usize, height: usize, shape: Shape) -> Self {
+        Self {
+            width,
+            height,
+            shape,
+            mask: None,
+        }
+    }
+
+    /// Creates a grid of the given shape with the cells of `mask`.
+    #[inline]
+    pub fn masked(mask: Mask, shape: Shape) -> Self {
+        Self {
+            width: mask.width(),
+            height: mask.height(),
+            shape,
+            mask: Some(mask),
+        }
+    }
+}
+
+impl Topology for Grid {
+    #[inline]
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    fn cell_count(&self) -> usize {
+        match &self.mask {
+            Some(mask) => mask.cell_count(),
+            None => self.width * self.height,
+        }
+    }
+
+    #[inline]
+    fn to_index(&self, pos: Position) -> Option<usize> {
+        match &self.mask {
+            Some(mask) => mask.index(pos),
+            None => (pos.x < self.width && pos.y < self.height).then(|| pos.to_index(self.width)),
+        }
+    }
+
+    #[inline]
+    fn to_position(&self, index: usize) -> Position {
+        match &self.mask {
+            Some(mask) => mask.position(index),
+            None => Position::from_index(index, self.width),
+        }
+    }
+
+    fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> {
+        self.shape
+            .neighbors(pos, self.width, self.height)
+            .filter(|&n| self.mask.as_ref().is_none_or(|mask| mask.contains(n)))
+    }
+
+    #[inline]
+    fn max_neighbors(&self) -> usize {
+        match self.shape {
+            Shape::Hex => 6,
+            Shape::Bounded | Shape::Torus => 8,
+        }
+    }
+
+    #[inline]
+    fn is_bounded_rectangle(&self) -> bool {
+        self.shape == Shape::Bounded && self.mask.is_none()
+    }
+}
+
+/// [`Mask`] marks the positions of a rectangle which are cells of a board, for boards
+/// shaped like hearts, letters or with holes. Cells are numbered in row-major order,
+/// skipping the positions outside the mask. Masks are written as rows of `#` for cells and
+/// `.` for positions outside the mask, and can be parsed from the same rows or from the
+/// [`Display`] of a board, where positions outside the mask are blank.
+///
+/// [`Display`]: std::fmt::Display
+///
+/// # Examples
+/// ```
+/// use mineraker::position::Position;
+/// use mineraker::topology::Mask;
+///
+/// let mask: Mask = ".#.\n###\n".parse().unwrap();
+/// assert_eq!(mask.cell_count(), 4);
+/// assert_eq!(mask.index(Position::new(0, 0)), None);
+/// assert_eq!(mask.index(Position::new(0, 1)), Some(1));
+/// assert_eq!(mask.position(3), Position::new(2, 1));
+/// assert_eq!(mask.to_string(), ".#.\n###\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    /// Index of the cell at each position of the rectangle in row-major order.
+    indexes: Vec<Option<usize>>,
+    /// Row-major index of the position of each cell.
+    positions: Vec<usize>,
+}
+
+impl Mask {
+    /// Creates a mask of the given width and height containing the positions for which
+    /// `is_cell` returns `true`.
+    pub fn from_fn(width: usize, height: usize, mut is_cell: impl FnMut(Position) -> bool) -> Self {
+        let mut positions = Vec::new();
+        let indexes = (0..width * height)
+            .map(|i| {
+                is_cell(Position::from_index(i, width)).then(|| {
+                    positions.push(i);
+                    positions.len() - 1
+                })
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            indexes,
+            positions,
+        }
+    }
+
+    /// Returns the width of the rectangle containing the mask.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the rectangle containing the mask.
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of cells inside the mask.
+    #[inline]
+    pub fn cell_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns `true` if `pos` is inside the mask.
+    #[inline]
+    pub fn contains(&self, pos: Position) -> bool {
+        self.index(pos).is_some()
+    }
+
+    /// Returns the index of the cell at `pos`, or [`None`] if `pos` is outside the mask.
+    #[inline]
+    pub fn index(&self, pos: Position) -> Option<usize> {
+        if pos.x < self.width && pos.y < self.height {
+            self.indexes[pos.to_index(self.width)]
+        } else {
+            None
+        }
+    }
+
+    /// Returns the position of the cell with the given index.
+    ///
+    /// # Panics
+    /// If index is not less than [`Mask::cell_count`].
+    #[inline]
+    pub fn position(&self, index: usize) -> Position {
+        Position::from_index(self.positions[index], self.width)
+    }
+}
+
+impl std::fmt::Display for Mask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.indexes.chunks(self.width.max(1)) {
+            for index in row {
+                write!(f, "{}", if index.is_some() { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// [`Mask`] is serialized as its rows of `#` and `.`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Mask {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Mask {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = String::deserialize(deserializer)?;
+        rows.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// [`Lattice`] is a box of cells in N dimensions, where every cell is connected to the
+/// `3^N - 1` cells which differ from it by at most one in every coordinate. Cells in three
+/// dimensions have up to 26 neighbors. Cells are identified by [`Point`]s, and laid out in
+/// a rectangle as wide as the first dimension, where the rows of every slice through the
+/// first two dimensions follow the rows of the previous slice. [`Lattice::position`] and
+/// [`Lattice::point`] convert between the two.
+///
+/// # Examples
+/// ```
+/// use mineraker::board::{Board, GenerationSettings};
+/// use mineraker::position::Point;
+/// use mineraker::topology::{Lattice, Topology};
+///
+/// let cube = Lattice::new([4, 4, 4]);
+/// let center = cube.position(Point::new([1, 2, 3]));
+/// assert_eq!((cube.width(), cube.height()), (4, 16));
+/// assert_eq!(cube.point(center), Point::new([1, 2, 3]));
+///
+/// let gs = GenerationSettings {
+///     mine_count: 10,
+///     start_pos: center,
+///     ..Default::default()
+/// };
+/// let board = Board::try_with_topology(cube, &gs).unwrap();
+/// assert_eq!(board.neighbors(center).count(), 17);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lattice<const N: usize> {
+    dims: [usize; N],
+}
+
+impl<const N: usize> Lattice<N> {
+    /// Creates a lattice with the given length in every dimension. Lattices without
+    /// dimensions don't compile.
+    ///
+    /// # Panics
+    /// If the number of cells overflows [`usize`].
+    ///
+    /// ```compile_fail
+    /// use mineraker::topology::Lattice;
+    ///
+    /// let point = Lattice::new([]);
+    /// ```
+    pub fn new(dims: [usize; N]) -> Self {
+        const { assert!(N > 0, "lattice must have at least one dimension") };
+        assert!(
+            dims.iter()
+                .try_fold(1usize, |n, &len| n.checked_mul(len))
+                .is_some(),
+            "lattice dimensions {:?} overflow the number of cells",
+            dims
+        );
+        Self { dims }
+    }
+
+    /// Returns the length of the lattice in every dimension.
+    #[inline]
+    pub fn dims(&self) -> [usize; N] {
+        self.dims
+    }
+
+    /// Returns the number of slices through the first two dimensions.
+    #[inline]
+    pub fn slice_count(&self) -> usize {
+        self.dims.iter().skip(2).product()
+    }
+
+    /// Returns the point of the cell laid out at `pos`, which must be inside the rectangle
+    /// of the lattice.
+    #[inline]
+    pub fn point(&self, pos: Position) -> Point<N> {
+        Point::from_index(pos.to_index(self.width()), self.dims)
+    }
+
+    /// Returns the position where the cell at `point` is laid out. Point must be inside
+    /// the lattice.
+    #[inline]
+    pub fn position(&self, point: Point<N>) -> Position {
+        Position::from_index(point.to_index(self.dims), self.width())
+    }
+}
+
+impl<const N: usize> Topology for Lattice<N> {
+    #[inline]
+    fn width(&self) -> usize {
+        self.dims[0]
+    }
+
+    #[inline]
+    fn height(&self) -> usize {
+        self.dims[1..].iter().product()
+    }
+
+    #[inline]
+    fn cell_count(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    #[inline]
+    fn to_index(&self, pos: Position) -> Option<usize> {
+        (pos.x < self.width() && pos.y < self.height()).then(|| pos.to_index(self.width()))
+    }
+
+    #[inline]
+    fn to_position(&self, index: usize) -> Position {
+        Position::from_index(index, self.width())
+    }
+
+    fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> {
+        let inside = self.contains(pos);
+        inside
+            .then(|| self.point(pos).neighbors(self.dims))
+            .into_iter()
+            .flatten()
+            .map(|point| self.position(point))
+    }
+
+    /// Cells see up to three coordinates in every dimension, fewer in short dimensions.
+    fn max_neighbors(&self) -> usize {
+        self.dims
+            .iter()
+            .map(|&len| len.min(3))
+            .fold(1usize, |n, len| n.saturating_mul(len))
+            .saturating_sub(1)
+    }
+
+    #[inline]
+    fn is_bounded_rectangle(&self) -> bool {
+        N == 2
+    }
+}
+
+/// [`Shape`] determines how the tiles of a [`Grid`] are connected to each other.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    /// Board is a rectangle with edges. Tiles on the edges have fewer neighbors.
+    #[default]
+    Bounded,
+    /// Edges of the board wrap around to the opposite edges, like on the surface of a
+    /// torus. Every tile has eight neighbors on boards of at least 3x3 tiles.
+    Torus,
+    /// Tiles are hexagons in offset coordinates, where odd rows are shifted right by half
+    /// a tile. Every tile has six neighbors: two in its own row and two in each of the
+    /// rows above and below it. Tiles on the edges have fewer neighbors.
+    Hex,
+}
+
+impl Shape {
+    /// Returns iterator over the neighbor [`Position`]s of `pos` on a board of the given
+    /// width and height. Positions are returned in row-major order of their offsets from
+    /// `pos`. On a [`Shape::Torus`] narrower than three tiles the same tile can be
+    /// reached through both edges, but it is returned only once and `pos` is never its own
+    /// neighbor.
+    ///
+    /// # Examples
+    /// ```
+    /// use mineraker::position::Position;
+    /// use mineraker::topology::Shape;
+    ///
+    /// let corner = Position::new(0, 0);
+    /// assert_eq!(Shape::Bounded.neighbors(corner, 4, 4).count(), 3);
+    ///
+    /// let neighbors: Vec<Position> = Shape::Torus.neighbors(corner, 4, 4).collect();
+    /// assert_eq!(neighbors, [
+    ///     Position::new(3, 3),
+    ///     Position::new(0, 3),
+    ///     Position::new(1, 3),
+    ///     Position::new(3, 0),
+    ///     Position::new(1, 0),
+    ///     Position::new(3, 1),
+    ///     Position::new(0, 1),
+    ///     Position::new(1, 1),
+    /// ]);
+    ///
+    /// // Odd rows are shifted right, so their diagonal neighbors are further right.
+    /// let neighbors: Vec<Position> = Shape::Hex.neighbors(Position::new(1, 1), 4, 4).collect();
+    /// assert_eq!(neighbors, [
+    ///     Position::new(1, 0),
+    ///     Position::new(2, 0),
+    ///     Position::new(0, 1),
+    ///     Position::new(2, 1),
+    ///     Position::new(1, 2),
+    ///     Position::new(2, 2),
+    /// ]);
+    /// ```
+    pub fn neighbors(
+        self,
+        pos: Position,
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = Position> {
+        let mut neighbors = [None; 8];
+        match self {
+            Shape::Bounded => {
+                for (slot, n) in neighbors.iter_mut().zip(pos.neighbors(width, height)) {
+                    *slot = Some(n);
+                }
+            }
+            Shape::Torus if pos.x < width && pos.y < height => {
+                let wrap = |c: usize, d: usize, len: usize| (c + d + len - 1) % len;
+                let mut count = 0;
+                for dy in 0..3 {
+                    for dx in (0..3).filter(|&dx| (dx, dy) != (1, 1)) {
+                        let n = Position::new(wrap(pos.x, dx, width), wrap(pos.y, dy, height));
+                        if n != pos && !neighbors[..count].contains(&Some(n)) {
+                            neighbors[count] = Some(n);
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            Shape::Torus => {}
+            Shape::Hex => {
+                let (x, y) = (pos.x, pos.y);
+                // Rows above and below an odd row reach half a tile further right.
+                let diagonal = x + y % 2;
+                let candidates = [
+                    Position::new(diagonal.wrapping_sub(1), y.wrapping_sub(1)),
+                    Position::new(diagonal, y.wrapping_sub(1)),
+                    Position::new(x.wrapping_sub(1), y),
+                    Position::new(x + 1, y),
+                    Position::new(diagonal.wrapping_sub(1), y + 1),
+                    Position::new(diagonal, y + 1),
+                ];
+                let inside = candidates
+                    .into_iter()
+                    .filter(|n| n.x < width && n.y < height);
+                for (slot, n) in neighbors.iter_mut().zip(inside) {
+                    *slot = Some(n);
+                }
+            }
+        }
+        neighbors.into_iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Board, BoardError, GenerationMode, GenerationSettings, Seed, StartPolicy},
+        game::Move,
+        position::{Point, Position},
+        solver,
+        tile::{State, Value},
+        topology::{Grid, Lattice, Mask, Shape, Topology},
+    };
+
+    /// Square board where tiles are connected by knight moves.
+    #[derive(Debug, Clone)]
+    struct Knights(usize);
+
+    impl Topology for Knights {
+        fn width(&self) -> usize {
+            self.0
+        }
+
+        fn height(&self) -> usize {
+            self.0
+        }
+
+        fn cell_count(&self) -> usize {
+            self.0 * self.0
+        }
+
+        fn to_index(&self, pos: Position) -> Option<usize> {
+            (pos.x < self.0 && pos.y < self.0).then(|| pos.to_index(self.0))
+        }
+
+        fn to_position(&self, index: usize) -> Position {
+            Position::from_index(index, self.0)
+        }
+
+        fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> {
+            const JUMPS: [(isize, isize); 8] = [
+                (-1, -2),
+                (1, -2),
+                (-2, -1),
+                (2, -1),
+                (-2, 1),
+                (2, 1),
+                (-1, 2),
+                (1, 2),
+            ];
+            JUMPS.into_iter().filter_map(move |(dx, dy)| {
+                let x = pos.x.checked_add_signed(dx)?;
+                let y = pos.y.checked_add_signed(dy)?;
+                (x < self.0 && y < self.0).then_some(Position::new(x, y))
+            })
+        }
+    }
+
+    /// Grid with the tiles of a rectangle in the middle removed. Cells are numbered in
+    /// row-major order, skipping the hole.
+    #[derive(Debug, Clone)]
+    struct Ring {
+        grid: Grid,
+        hole: (std::ops::Range<usize>, std::ops::Range<usize>),
+    }
+
+    impl Ring {
+        fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+            (0..self.grid.cell_count())
+                .map(|i| self.grid.to_position(i))
+                .filter(|pos| !self.hole.0.contains(&pos.x) || !self.hole.1.contains(&pos.y))
+        }
+    }
+
+    impl Topology for Ring {
+        fn width(&self) -> usize {
+            self.grid.width
+        }
+
+        fn height(&self) -> usize {
+            self.grid.height
+        }
+
+        fn cell_count(&self) -> usize {
+            self.positions().count()
+        }
+
+        fn to_index(&self, pos: Position) -> Option<usize> {
+            self.positions().position(|p| p == pos)
+        }
+
+        fn to_position(&self, index: usize) -> Position {
+            self.positions().nth(index).unwrap()
+        }
+
+        fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> {
+            self.grid.neighbors(pos).filter(|&n| self.contains(n))
+        }
+    }
+
+    fn settings(seed: u64, mine_count: usize) -> GenerationSettings {
+        GenerationSettings {
+            seed: Seed::new(seed),
+            mine_count,
+            start_pos: Position::new(0, 0),
+            start_policy: StartPolicy::SafeNeighborhood,
+            ..Default::default()
+        }
+    }
+
+    fn assert_numbers_count_mines<T: Topology>(board: &Board<T>) {
+        for (pos, tile) in board.tiles_and_pos() {
+            let mines = board
+                .neighbors_tile_and_pos(pos)
+                .filter(|(_, tile)| tile.value() == Value::Mine)
+                .count();
+            if let Value::Near(n) = tile.value() {
+                assert_eq!(n as usize, mines);
+            }
+        }
+    }
+
+    #[test]
+    fn grid_indexes_round_trip() {
+        let grid = Grid::new(7, 3, Shape::Torus);
+
+        assert_eq!(grid.cell_count(), 21);
+        for i in 0..grid.cell_count() {
+            assert_eq!(grid.to_index(grid.to_position(i)), Some(i));
+        }
+        assert_eq!(grid.to_index(Position::new(7, 0)), None);
+        assert!(grid.is_bounded_rectangle() != (grid.shape == Shape::Torus));
+    }
+
+    #[test]
+    fn knight_boards_generate_and_cascade() {
+        for seed in 0..10 {
+            let mut board = Board::try_with_topology(Knights(8), &settings(seed, 10)).unwrap();
+            assert_numbers_count_mines(&board);
+            assert_eq!(board.mine_count(), 10);
+
+            // Start and the tiles a knight's move away are protected.
+            let start = Position::new(0, 0);
+            assert!(board
+                .neighbors_tile_and_pos(start)
+                .all(|(_, tile)| tile.value() != Value::Mine));
+            let opened = board.open_from(start);
+            assert!(opened.len() >= 3);
+            assert!(board
+                .neighbors_tile_and_pos(start)
+                .all(|(_, tile)| tile.state() == State::Open));
+
+            for mv in solver::deduce(&board) {
+                let is_mine = board.get_tile(mv.pos()).unwrap().value() == Value::Mine;
+                assert_eq!(is_mine, matches!(mv, Move::Flag(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn boards_with_holes_skip_missing_tiles() {
+        let ring = Ring {
+            grid: Grid::new(12, 12, Shape::Bounded),
+            hole: (4..8, 4..8),
+        };
+        assert_eq!(ring.cell_count(), 128);
+
+        for seed in 0..5 {
+            let gs = GenerationSettings {
+                mode: GenerationMode::NoGuess { max_attempts: 1000 },
+                ..settings(seed, 10)
+            };
+            let board = Board::try_with_topology(ring.clone(), &gs).unwrap();
+            assert_numbers_count_mines(&board);
+            assert!(board.get_tile(Position::new(5, 5)).is_none());
+            assert_eq!(board.to_string().lines().nth(5), Some("####    ####"));
+            assert!(solver::is_solvable(&board, gs.start_pos));
+        }
+    }
+
+    #[test]
+    fn masked_grids_only_use_cells_inside_the_mask() {
+        let mask: Mask = "\
+.##...##.
+####.####
+#########
+.#######.
+..#####..
+...###...
+....#....
+"
+        .parse()
+        .unwrap();
+        assert_eq!(mask.cell_count(), 37);
+        let gs = GenerationSettings {
+            start_pos: Position::new(4, 2),
+            ..settings(0, 8)
+        };
+
+        for seed in 0..10 {
+            let gs = GenerationSettings {
+                seed: Seed::new(seed),
+                ..gs
+            };
+            let board =
+                Board::try_with_topology(Grid::masked(mask.clone(), Shape::Bounded), &gs).unwrap();
+            assert_numbers_count_mines(&board);
+            assert_eq!(board.mine_count(), 8);
+            assert_eq!(board.tiles_and_pos().count(), 37);
+            assert!(board.tiles_and_pos().all(|(pos, _)| mask.contains(pos)));
+            assert!(board.get_tile(Position::new(0, 0)).is_none());
+            // Top of the left lobe has no neighbors in the notch or above the lobes.
+            let neighbors: Vec<_> = board.neighbors(Position::new(2, 0)).collect();
+            assert_eq!(
+                neighbors,
+                [(1, 0), (1, 1), (2, 1), (3, 1)].map(Position::from)
+            );
+            let rows: Vec<_> = board.to_string().lines().map(String::from).collect();
+            assert_eq!(rows[0], " ##   ## ");
+            assert_eq!(rows[6], "    #    ");
+        }
+
+        let grid = Grid::masked(mask, Shape::Bounded);
+        let no_guess = GenerationSettings {
+            mode: GenerationMode::NoGuess { max_attempts: 1000 },
+            ..gs
+        };
+        let board = Board::try_with_topology(grid.clone(), &no_guess).unwrap();
+        assert!(solver::is_solvable(&board, no_guess.start_pos));
+
+        // Size of the mask replaces the size of the settings.
+        let wrong_size = GenerationSettings { width: 10, ..gs };
+        assert_eq!(
+            Board::try_with_topology(grid.clone(), &wrong_size)
+                .unwrap()
+                .to_string(),
+            Board::try_with_topology(grid, &gs).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn cube_boards_count_all_26_neighbors() {
+        let cube = Lattice::new([3, 3, 3]);
+        let center = cube.position(Point::new([1, 1, 1]));
+        let board = Board::from_mines(cube, (0..27).filter(|&i| i != 13));
+
+        assert_eq!(cube.to_index(center), Some(13));
+        assert_eq!(board.get_tile(center).unwrap().value(), Value::Near(26));
+        assert_eq!(
+            board.solution().slices().to_string().lines().nth(7),
+            Some("*q*")
+        );
+    }
+
+    #[test]
+    fn cube_boards_generate_cascade_and_deduce() {
+        let cube = Lattice::new([6, 5, 4]);
+        let start = cube.position(Point::new([2, 2, 2]));
+        for seed in 0..5 {
+            let gs = GenerationSettings {
+                start_pos: start,
+                mode: GenerationMode::NoGuess { max_attempts: 1000 },
+                ..settings(seed, 12)
+            };
+            let mut board = Board::try_with_topology(cube, &gs).unwrap();
+            assert_numbers_count_mines(&board);
+            assert_eq!(board.mine_count(), 12);
+            assert!(solver::is_solvable(&board, start));
+
+            // Cascades cross slices, so the start opens tiles above and below its slice.
+            let opened = board.open_from(start);
+            let slices: Vec<usize> = opened.iter().map(|&pos| cube.point(pos).0[2]).collect();
+            assert!(slices.contains(&1) && slices.contains(&3));
+            for mv in solver::deduce(&board) {
+                let is_mine = board.get_tile(mv.pos()).unwrap().value() == Value::Mine;
+                assert_eq!(is_mine, matches!(mv, Move::Flag(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_which_dont_fit_in_tiles_are_errors() {
+        assert_eq!(Lattice::new([3, 3, 3]).max_neighbors(), 26);
+        assert_eq!(Lattice::new([2, 2, 2, 2]).max_neighbors(), 15);
+        assert_eq!(Knights(5).max_neighbors(), 8);
+
+        let hypercube = Lattice::new([3, 3, 3, 3]);
+        let gs = GenerationSettings {
+            start_policy: StartPolicy::Unprotected,
+            ..settings(0, 80)
+        };
+        assert_eq!(
+            Board::try_with_topology(hypercube, &gs).unwrap_err(),
+            BoardError::NumberOverflow(80)
+        );
+        let sparse = GenerationSettings {
+            mine_count: 20,
+            ..gs
+        };
+        assert!(Board::try_with_topology(hypercube, &sparse).is_ok());
+
+        let stacked = GenerationSettings {
+            mine_count: 78,
+            mines_per_cell: 3,
+            ..gs
+        };
+        assert_eq!(
+            Board::try_with_topology(Lattice::new([3, 3, 3]), &stacked).unwrap_err(),
+            BoardError::NumberOverflow(78)
+        );
+    }
+}